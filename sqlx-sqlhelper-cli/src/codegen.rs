@@ -0,0 +1,323 @@
+use std::collections::BTreeSet;
+
+use inflector::Inflector;
+
+use crate::schema::{Column, Table};
+
+/// Rust关键字，字段名与关键字相同时添加`_`后缀
+const KEYWORDS: [&str; 38] = [
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use",
+    "where", "while", "yield",
+];
+
+/// `create_time`字段的常用名字
+const CREATE_TIME_NAMES: [&str; 4] = ["create_time", "created_at", "gmt_create", "create_at"];
+/// `update_time`字段的常用名字
+const UPDATE_TIME_NAMES: [&str; 4] = ["update_time", "updated_at", "gmt_modified", "update_at"];
+
+/// 生成一个表对应的Rust源文件
+pub fn generate(table: &Table) -> String {
+    let struct_name = table.name.to_pascal_case();
+    let primary_key = table.primary_key();
+    let mut imports = BTreeSet::new();
+    let mut fields = Vec::new();
+    for column in &table.columns {
+        let (ty, import) = rust_type(column);
+        if let Some(import) = import {
+            imports.insert(import);
+        }
+        fields.push(generate_field(column, &ty, primary_key.is_some()));
+    }
+
+    let mut code = String::from("// 由sqlx-sqlhelper-cli生成\n");
+    for import in &imports {
+        code.push_str(&format!("use {};\n", import));
+    }
+    if primary_key.is_some() {
+        code.push_str("use sqlx_sqlhelper::SqlHelper;\n\nuse super::db;\n");
+    }
+    code.push('\n');
+    push_doc(&mut code, "", &table.comment);
+    match primary_key {
+        Some(_) => {
+            code.push_str("#[derive(sqlx::FromRow, Debug, SqlHelper)]\n");
+            if struct_name.to_snake_case() != table.name {
+                code.push_str(&format!("#[sql_helper(table = \"{}\")]\n", table.name));
+            }
+        }
+        None => {
+            code.push_str("/// 没有单列主键，未生成`SqlHelper`\n");
+            code.push_str("#[derive(sqlx::FromRow, Debug)]\n");
+        }
+    }
+    code.push_str(&format!("pub struct {} {{\n", struct_name));
+    code.push_str(&fields.join(""));
+    code.push_str("}\n");
+    code
+}
+
+/// 表对应的模块名，也是生成的文件名
+pub fn module_name(table: &Table) -> String {
+    field_name(&table.name)
+}
+
+fn generate_field(column: &Column, ty: &str, sql_helper: bool) -> String {
+    let field_name = field_name(&column.name);
+    let mut field = String::new();
+    push_doc(&mut field, "    ", &column.comment);
+    if sql_helper {
+        if column.is_primary_key() {
+            field.push_str("    #[id]\n");
+        } else if is_auto_time(column) && CREATE_TIME_NAMES.contains(&column.name.as_str()) {
+            field.push_str("    #[create_time]\n");
+        } else if is_auto_time(column) && UPDATE_TIME_NAMES.contains(&column.name.as_str()) {
+            field.push_str("    #[update_time]\n");
+        } else if column.key == "UNI" {
+            field.push_str("    #[unique]\n");
+        }
+    }
+    if field_name != column.name {
+        if sql_helper {
+            field.push_str(&format!("    #[field_name(\"{}\")]\n", column.name));
+        }
+        field.push_str(&format!("    #[sqlx(rename = \"{}\")]\n", column.name));
+    }
+    field.push_str(&format!("    pub {}: {},\n", field_name, ty));
+    field
+}
+
+/// 字段注释转换为文档注释
+fn push_doc(code: &mut String, indent: &str, comment: &str) {
+    for line in comment
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+    {
+        code.push_str(&format!("{}/// {}\n", indent, line));
+    }
+}
+
+/// 表字段名转换为合法的snake_case字段名
+fn field_name(column: &str) -> String {
+    let mut name = column
+        .to_snake_case()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    if KEYWORDS.contains(&name.as_str()) {
+        name.push('_');
+    }
+    name
+}
+
+/// 可以自动填充的时间字段，可以为`NULL`的字段生成`Option<NaiveDateTime>`，`SqlHelper`填充为`Some(当前时间)`
+fn is_auto_time(column: &Column) -> bool {
+    matches!(column.data_type.as_str(), "datetime" | "timestamp")
+}
+
+/// MySQL类型对应的Rust类型，以及需要引入的类型
+fn rust_type(column: &Column) -> (String, Option<&'static str>) {
+    let unsigned = column.is_unsigned();
+    let (ty, import) = match column.data_type.as_str() {
+        "tinyint" if column.column_type.to_lowercase().starts_with("tinyint(1)") => ("bool", None),
+        "bit" if column.column_type.to_lowercase() == "bit(1)" => ("bool", None),
+        "tinyint" if unsigned => ("u8", None),
+        "tinyint" => ("i8", None),
+        "smallint" if unsigned => ("u16", None),
+        "smallint" => ("i16", None),
+        "mediumint" | "int" | "integer" if unsigned => ("u32", None),
+        "mediumint" | "int" | "integer" => ("i32", None),
+        "bigint" if unsigned => ("u64", None),
+        "bigint" => ("i64", None),
+        "year" => ("u16", None),
+        "float" => ("f32", None),
+        "double" | "real" => ("f64", None),
+        "decimal" | "numeric" => ("Decimal", Some("rust_decimal::Decimal")),
+        "datetime" | "timestamp" => ("NaiveDateTime", Some("chrono::NaiveDateTime")),
+        "date" => ("NaiveDate", Some("chrono::NaiveDate")),
+        "time" => ("NaiveTime", Some("chrono::NaiveTime")),
+        "binary" | "varbinary" | "tinyblob" | "blob" | "mediumblob" | "longblob" | "bit" => {
+            ("Vec<u8>", None)
+        }
+        _ => ("String", None),
+    };
+    match column.nullable {
+        true => (format!("Option<{}>", ty), import),
+        false => (ty.to_string(), import),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    fn column(name: &str, column_type: &str, nullable: bool, key: &str, comment: &str) -> Column {
+        Column {
+            name: name.to_string(),
+            data_type: column_type
+                .split(|c: char| c == '(' || c.is_whitespace())
+                .next()
+                .unwrap_or_default()
+                .to_string(),
+            column_type: column_type.to_string(),
+            nullable,
+            key: key.to_string(),
+            comment: comment.to_string(),
+        }
+    }
+
+    /// `tests/fixtures/tb_user_info.rs`对应的表
+    pub(crate) fn user_info_table() -> Table {
+        Table {
+            name: "tb_user_info".to_string(),
+            comment: "用户信息\n  包含登录账号 ".to_string(),
+            columns: vec![
+                column("id", "bigint(20) unsigned", false, "PRI", "主键"),
+                column("account", "varchar(64)", false, "UNI", "登录账号"),
+                column("nickName", "varchar(32)", true, "", ""),
+                column("type", "tinyint(4)", false, "", ""),
+                column("enabled", "tinyint(1)", false, "", ""),
+                column("avatar", "blob", true, "", ""),
+                column("birthday", "date", true, "", ""),
+                column("create_time", "datetime", false, "", "创建时间"),
+                column("updated_at", "datetime", true, "", "更新时间"),
+            ],
+        }
+    }
+
+    fn rust_type_of(column_type: &str, nullable: bool) -> String {
+        rust_type(&column("c", column_type, nullable, "", "")).0
+    }
+
+    #[test]
+    fn type_mapping() {
+        assert_eq!(rust_type_of("tinyint(1)", false), "bool");
+        assert_eq!(rust_type_of("bit(1)", false), "bool");
+        assert_eq!(rust_type_of("bit(8)", false), "Vec<u8>");
+        assert_eq!(rust_type_of("tinyint(4)", false), "i8");
+        assert_eq!(rust_type_of("tinyint(3) unsigned", false), "u8");
+        assert_eq!(rust_type_of("smallint(6)", false), "i16");
+        assert_eq!(rust_type_of("int(10) unsigned", false), "u32");
+        assert_eq!(rust_type_of("mediumint(9)", false), "i32");
+        assert_eq!(rust_type_of("bigint(20)", false), "i64");
+        assert_eq!(rust_type_of("bigint(20) UNSIGNED", false), "u64");
+        assert_eq!(rust_type_of("double", false), "f64");
+        assert_eq!(rust_type_of("float", false), "f32");
+        assert_eq!(rust_type_of("varchar(255)", false), "String");
+        assert_eq!(rust_type_of("json", false), "String");
+        assert_eq!(rust_type_of("varbinary(16)", false), "Vec<u8>");
+        assert_eq!(rust_type_of("date", false), "NaiveDate");
+        assert_eq!(rust_type_of("timestamp", true), "Option<NaiveDateTime>");
+        assert_eq!(rust_type_of("int(11)", true), "Option<i32>");
+        assert_eq!(
+            rust_type(&column("price", "decimal(10,2)", false, "", "")),
+            ("Decimal".to_string(), Some("rust_decimal::Decimal"))
+        );
+        assert_eq!(
+            rust_type(&column("time", "datetime", false, "", "")).1,
+            Some("chrono::NaiveDateTime")
+        );
+    }
+
+    #[test]
+    fn field_names() {
+        assert_eq!(field_name("account"), "account");
+        assert_eq!(field_name("nickName"), "nick_name");
+        assert_eq!(field_name("UserID"), "user_id");
+        assert_eq!(field_name("type"), "type_");
+        assert_eq!(field_name("1st"), "_1st");
+        assert_eq!(field_name("order-no"), "order_no");
+        assert_eq!(module_name(&user_info_table()), "tb_user_info");
+    }
+
+    #[test]
+    fn field_name_attr() {
+        //字段名与表字段名不一致时添加`#[field_name]`和`#[sqlx(rename)]`
+        let nick_name = column("nickName", "varchar(32)", true, "", "");
+        assert_eq!(
+            generate_field(&nick_name, "Option<String>", true),
+            "    #[field_name(\"nickName\")]\n    #[sqlx(rename = \"nickName\")]\n    pub nick_name: Option<String>,\n"
+        );
+        //没有生成`SqlHelper`时只添加`#[sqlx(rename)]`
+        assert_eq!(
+            generate_field(&nick_name, "Option<String>", false),
+            "    #[sqlx(rename = \"nickName\")]\n    pub nick_name: Option<String>,\n"
+        );
+        let account = column("account", "varchar(64)", false, "", "");
+        assert_eq!(
+            generate_field(&account, "String", true),
+            "    pub account: String,\n"
+        );
+    }
+
+    #[test]
+    fn doc_comments() {
+        let mut code = String::new();
+        push_doc(&mut code, "    ", " 第一行\n\n  第二行 \n");
+        assert_eq!(code, "    /// 第一行\n    /// 第二行\n");
+        let mut code = String::new();
+        push_doc(&mut code, "", "");
+        assert_eq!(code, "");
+    }
+
+    #[test]
+    fn time_attrs() {
+        let field = |name: &str, column_type: &str, nullable: bool| {
+            generate_field(
+                &column(name, column_type, nullable, "", ""),
+                "NaiveDateTime",
+                true,
+            )
+        };
+        assert!(field("create_time", "datetime", false).starts_with("    #[create_time]\n"));
+        assert!(field("created_at", "timestamp", false).starts_with("    #[create_time]\n"));
+        assert!(field("gmt_modified", "datetime", false).starts_with("    #[update_time]\n"));
+        assert!(field("update_time", "datetime", false).starts_with("    #[update_time]\n"));
+        //可以为`NULL`的时间字段同样添加
+        assert!(field("create_time", "datetime", true).starts_with("    #[create_time]\n"));
+        assert!(field("updated_at", "timestamp", true).starts_with("    #[update_time]\n"));
+        //不是时间类型的字段、不是常用名字的字段不添加
+        assert!(!field("create_time", "varchar(32)", false).contains("#[create_time]"));
+        assert!(!field("login_time", "datetime", false).contains("#["));
+    }
+
+    #[test]
+    fn key_attrs() {
+        let table = user_info_table();
+        let code = generate(&table);
+        assert!(code.contains("    #[id]\n    pub id: u64,\n"));
+        assert!(code.contains("    #[unique]\n    pub account: String,\n"));
+
+        //联合主键不生成`SqlHelper`
+        let mut table = user_info_table();
+        table.columns[1].key = "PRI".to_string();
+        let code = generate(&table);
+        assert!(
+            code.contains("/// 没有单列主键，未生成`SqlHelper`\n#[derive(sqlx::FromRow, Debug)]\n")
+        );
+        assert!(!code.contains("#[id]"));
+        assert!(!code.contains("use super::db;"));
+    }
+
+    #[test]
+    fn table_name_attr() {
+        let mut table = user_info_table();
+        table.name = "TbUserInfo".to_string();
+        assert!(generate(&table).contains("#[sql_helper(table = \"TbUserInfo\")]\n"));
+        assert!(!generate(&user_info_table()).contains("#[sql_helper(table"));
+    }
+
+    /// 生成的代码与`tests/fixtures/tb_user_info.rs`一致，该文件在`main.rs`中通过`SqlHelper`派生宏编译
+    #[test]
+    fn generated_fixture() {
+        assert_eq!(
+            generate(&user_info_table()),
+            include_str!("../tests/fixtures/tb_user_info.rs")
+        );
+    }
+}
@@ -0,0 +1,99 @@
+use sqlx::{MySql, Pool};
+
+/// 数据库中的表
+pub struct Table {
+    pub name: String,
+    pub comment: String,
+    pub columns: Vec<Column>,
+}
+
+impl Table {
+    /// 单列主键，联合主键或者没有主键时返回`None`
+    pub fn primary_key(&self) -> Option<&Column> {
+        let mut primary_keys = self.columns.iter().filter(|column| column.is_primary_key());
+        match (primary_keys.next(), primary_keys.next()) {
+            (Some(column), None) => Some(column),
+            _ => None,
+        }
+    }
+}
+
+/// 表中的字段，来自`information_schema.COLUMNS`
+pub struct Column {
+    pub name: String,
+    /// 小写的类型名字，例如`varchar`
+    pub data_type: String,
+    /// 完整的类型，例如`int(10) unsigned`
+    pub column_type: String,
+    pub nullable: bool,
+    /// `PRI`、`UNI`、`MUL`或者空字符串
+    pub key: String,
+    pub comment: String,
+}
+
+impl Column {
+    pub fn is_primary_key(&self) -> bool {
+        self.key == "PRI"
+    }
+
+    pub fn is_unsigned(&self) -> bool {
+        self.column_type.to_lowercase().contains("unsigned")
+    }
+}
+
+/// 读取当前数据库中的表结构，`tables`为空时读取所有表
+pub async fn load_tables(pool: &Pool<MySql>, tables: &[String]) -> Result<Vec<Table>, String> {
+    let table_rows = sqlx::query_as::<_, (String, String)>(
+        "SELECT CAST(TABLE_NAME AS CHAR), CAST(TABLE_COMMENT AS CHAR) \
+         FROM information_schema.TABLES \
+         WHERE TABLE_SCHEMA = DATABASE() AND TABLE_TYPE = 'BASE TABLE' ORDER BY TABLE_NAME",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if let Some(table) = tables
+        .iter()
+        .find(|table| !table_rows.iter().any(|(name, _)| name == *table))
+    {
+        return Err(format!("table `{}` does not exist", table));
+    }
+
+    let column_rows =
+        sqlx::query_as::<_, (String, String, String, String, String, String, String)>(
+            "SELECT CAST(TABLE_NAME AS CHAR), CAST(COLUMN_NAME AS CHAR), CAST(DATA_TYPE AS CHAR), \
+         CAST(COLUMN_TYPE AS CHAR), CAST(IS_NULLABLE AS CHAR), CAST(COLUMN_KEY AS CHAR), \
+         CAST(COLUMN_COMMENT AS CHAR) \
+         FROM information_schema.COLUMNS \
+         WHERE TABLE_SCHEMA = DATABASE() ORDER BY TABLE_NAME, ORDINAL_POSITION",
+        )
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(table_rows
+        .into_iter()
+        .filter(|(name, _)| tables.is_empty() || tables.contains(name))
+        .map(|(name, comment)| {
+            let columns = column_rows
+                .iter()
+                .filter(|row| row.0 == name)
+                .map(
+                    |(_, column_name, data_type, column_type, is_nullable, key, comment)| Column {
+                        name: column_name.clone(),
+                        data_type: data_type.to_lowercase(),
+                        column_type: column_type.clone(),
+                        nullable: is_nullable == "YES",
+                        key: key.clone(),
+                        comment: comment.clone(),
+                    },
+                )
+                .collect();
+            Table {
+                name,
+                comment,
+                columns,
+            }
+        })
+        .collect())
+}
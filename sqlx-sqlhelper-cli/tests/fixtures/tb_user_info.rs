@@ -0,0 +1,33 @@
+// 由sqlx-sqlhelper-cli生成
+use chrono::NaiveDate;
+use chrono::NaiveDateTime;
+use sqlx_sqlhelper::SqlHelper;
+
+use super::db;
+
+/// 用户信息
+/// 包含登录账号
+#[derive(sqlx::FromRow, Debug, SqlHelper)]
+pub struct TbUserInfo {
+    /// 主键
+    #[id]
+    pub id: u64,
+    /// 登录账号
+    #[unique]
+    pub account: String,
+    #[field_name("nickName")]
+    #[sqlx(rename = "nickName")]
+    pub nick_name: Option<String>,
+    #[field_name("type")]
+    #[sqlx(rename = "type")]
+    pub type_: i8,
+    pub enabled: bool,
+    pub avatar: Option<Vec<u8>>,
+    pub birthday: Option<NaiveDate>,
+    /// 创建时间
+    #[create_time]
+    pub create_time: NaiveDateTime,
+    /// 更新时间
+    #[update_time]
+    pub updated_at: Option<NaiveDateTime>,
+}
@@ -0,0 +1,300 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    bracketed,
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    Attribute, Error, Field, Ident, LitStr, Result, Token,
+};
+
+use crate::{
+    sql_helper::{get_field_attr, get_table_field_name},
+    INDEX_NAME, UNIQUE_NAME,
+};
+
+/// 字段或者struct上声明的索引
+pub struct Index {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub unique: bool,
+}
+
+/// struct或者字段上`#[index(...)]`、`#[unique(...)]`的参数
+#[derive(Default)]
+struct IndexArgs {
+    name: Option<LitStr>,
+    columns: Vec<(String, proc_macro2::Span)>,
+    unique: bool,
+}
+
+impl Parse for IndexArgs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut args = Self::default();
+        while !input.is_empty() {
+            let ident: Ident = input.parse()?;
+            match ident.to_string().as_str() {
+                "name" => {
+                    input.parse::<Token![=]>()?;
+                    args.name = Some(input.parse()?);
+                }
+                "columns" => {
+                    input.parse::<Token![=]>()?;
+                    let content;
+                    bracketed!(content in input);
+                    for column in Punctuated::<IndexColumn, Token![,]>::parse_terminated(&content)?
+                    {
+                        args.columns.push((column.0, column.1));
+                    }
+                }
+                "unique" => args.unique = true,
+                _ => {
+                    return Err(Error::new(
+                        ident.span(),
+                        format!("unsupported argument `{}` [index]", ident),
+                    ))
+                }
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        Ok(args)
+    }
+}
+
+/// `columns = [...]`中的字段，可以是字段名或者字符串形式的表字段名
+struct IndexColumn(String, proc_macro2::Span);
+
+impl Parse for IndexColumn {
+    fn parse(input: ParseStream) -> Result<Self> {
+        match input.peek(LitStr) {
+            true => {
+                let lit = input.parse::<LitStr>()?;
+                Ok(Self(lit.value(), lit.span()))
+            }
+            false => {
+                let ident = input.parse::<Ident>()?;
+                Ok(Self(ident.to_string(), ident.span()))
+            }
+        }
+    }
+}
+
+fn parse_index_args(attr: &Attribute) -> Result<IndexArgs> {
+    match attr.tokens.is_empty() {
+        true => Ok(IndexArgs::default()),
+        false => attr.parse_args(),
+    }
+}
+
+/// 获取字段上的`#[index]`、`#[unique]`以及struct上的`#[index(name = "...", columns = [...])]`
+///
+/// 未指定名字时，普通索引为`idx_<表名>_<字段名>`，唯一索引为`uk_<表名>_<字段名>`，
+/// 带上表名避免不同表的同名索引在同一个数据库中混淆
+pub fn get_indexes(table: &str, attrs: &[Attribute], fields: &[&Field]) -> Result<Vec<Index>> {
+    let mut indexes = Vec::new();
+    for field in fields {
+        for (attr_name, unique, prefix) in [(INDEX_NAME, false, "idx"), (UNIQUE_NAME, true, "uk")] {
+            let Some((_, attr)) = get_field_attr(field, attr_name) else {
+                continue;
+            };
+            let args = parse_index_args(attr)?;
+            if !args.columns.is_empty() {
+                return Err(Error::new_spanned(
+                    attr,
+                    "`columns` only supports struct [index]",
+                ));
+            }
+            let column = get_table_field_name(field);
+            indexes.push(Index {
+                name: args
+                    .name
+                    .map(|name| name.value())
+                    .unwrap_or_else(|| format!("{}_{}_{}", prefix, table, column)),
+                columns: vec![column],
+                unique: unique || args.unique,
+            });
+        }
+    }
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident(INDEX_NAME)) {
+        let args = parse_index_args(attr)?;
+        if args.columns.is_empty() {
+            return Err(Error::new_spanned(
+                attr,
+                "expected `columns = [...]` [index]",
+            ));
+        }
+        let mut columns = Vec::new();
+        for (column, span) in args.columns {
+            //字段名转换为表字段名
+            let column = match fields
+                .iter()
+                .find(|field| field.ident.as_ref().is_some_and(|ident| ident == &column))
+            {
+                Some(field) => get_table_field_name(field),
+                None if fields
+                    .iter()
+                    .any(|field| get_table_field_name(field) == column) =>
+                {
+                    column
+                }
+                None => {
+                    return Err(Error::new(
+                        span,
+                        format!("unknown column `{}` [index]", column),
+                    ))
+                }
+            };
+            columns.push(column);
+        }
+        let prefix = if args.unique { "uk" } else { "idx" };
+        indexes.push(Index {
+            name: args
+                .name
+                .map(|name| name.value())
+                .unwrap_or_else(|| format!("{}_{}_{}", prefix, table, columns.join("_"))),
+            columns,
+            unique: args.unique,
+        });
+    }
+    Ok(indexes)
+}
+
+/// 生成`IndexMeta`数组的表达式
+pub fn get_index_meta_quote(indexes: &[Index]) -> TokenStream2 {
+    let index_vec = indexes.iter().map(|index| {
+        let name = &index.name;
+        let columns = &index.columns;
+        let unique = index.unique;
+        quote!(
+            sqlx_sqlhelper::IndexMeta {
+                name: #name,
+                columns: &[#(#columns),*],
+                unique: #unique,
+            }
+        )
+    });
+    quote!(&[#(#index_vec),*])
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::{parse_quote, Data, DeriveInput, Fields};
+
+    use super::*;
+
+    fn indexes(input: DeriveInput) -> Result<Vec<Index>> {
+        let Data::Struct(data) = &input.data else {
+            unreachable!()
+        };
+        let Fields::Named(fields) = &data.fields else {
+            unreachable!()
+        };
+        let fields = fields.named.iter().collect::<Vec<_>>();
+        get_indexes("user", &input.attrs, &fields)
+    }
+
+    fn summary(indexes: &[Index]) -> Vec<(&str, Vec<&str>, bool)> {
+        indexes
+            .iter()
+            .map(|index| {
+                (
+                    index.name.as_str(),
+                    index.columns.iter().map(String::as_str).collect(),
+                    index.unique,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn field_indexes() {
+        let indexes = indexes(parse_quote! {
+            struct User {
+                id: u64,
+                #[unique]
+                email: String,
+                #[index]
+                #[field_name("nick_name")]
+                nick: String,
+                #[index(name = "idx_phone", unique)]
+                phone: String,
+            }
+        })
+        .unwrap();
+        assert_eq!(
+            summary(&indexes),
+            vec![
+                ("uk_user_email", vec!["email"], true),
+                ("idx_user_nick_name", vec!["nick_name"], false),
+                ("idx_phone", vec!["phone"], true),
+            ]
+        );
+    }
+
+    #[test]
+    fn struct_indexes() {
+        let indexes = indexes(parse_quote! {
+            #[index(columns = [org_id, "nick_name"])]
+            #[index(name = "uk_org_email", columns = [org_id, email], unique)]
+            struct User {
+                id: u64,
+                org_id: u64,
+                email: String,
+                #[field_name("nick_name")]
+                nick: String,
+            }
+        })
+        .unwrap();
+        assert_eq!(
+            summary(&indexes),
+            vec![
+                (
+                    "idx_user_org_id_nick_name",
+                    vec!["org_id", "nick_name"],
+                    false
+                ),
+                ("uk_org_email", vec!["org_id", "email"], true),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_indexes() {
+        let message = |input: DeriveInput| indexes(input).err().unwrap().to_string();
+        assert_eq!(
+            message(parse_quote! {
+                #[index(columns = [missing])]
+                struct User { id: u64 }
+            }),
+            "unknown column `missing` [index]"
+        );
+        assert_eq!(
+            message(parse_quote! {
+                #[index(name = "idx_empty")]
+                struct User { id: u64 }
+            }),
+            "expected `columns = [...]` [index]"
+        );
+        assert_eq!(
+            message(parse_quote! {
+                struct User {
+                    id: u64,
+                    #[index(columns = [id])]
+                    email: String,
+                }
+            }),
+            "`columns` only supports struct [index]"
+        );
+        assert_eq!(
+            message(parse_quote! {
+                struct User {
+                    id: u64,
+                    #[unique(desc)]
+                    email: String,
+                }
+            }),
+            "unsupported argument `desc` [index]"
+        );
+    }
+}
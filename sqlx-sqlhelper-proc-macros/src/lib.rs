@@ -2,26 +2,61 @@ use proc_macro::TokenStream;
 use syn::{parse_macro_input, ItemStruct};
 
 mod common_fields;
+mod index;
+mod meta;
+mod options;
+mod projection;
+mod relation;
 mod sql_helper;
+mod validate;
 
 pub(crate) const DEFAULT_ID_NAME: &str = "id";
 pub(crate) const DEFAULT_CREATE_TIME_NAME: &str = "create_time";
 pub(crate) const DEFAULT_UPDATE_TIME_NAME: &str = "update_time";
+pub(crate) const CREATED_BY_NAME: &str = "created_by";
+pub(crate) const UPDATED_BY_NAME: &str = "updated_by";
+pub(crate) const VERSION_NAME: &str = "version";
+pub(crate) const COLUMN_NAME: &str = "column";
+pub(crate) const INDEX_NAME: &str = "index";
+pub(crate) const UNIQUE_NAME: &str = "unique";
+pub(crate) const BELONGS_TO_NAME: &str = "belongs_to";
 
 /// 自动生成mysql数据库增删改查方法
 ///
 /// 基于sqlx生成`get_by_id`、`list`、`delete`、`add`、`update`、`save_or_update`、`new`、`new_common`、`base_page`、`base_count`等方法。
+/// 各属性生成的方法和行为详见[README](https://github.com/zzliujianbo/sqlx-sqlhelper#readme)。
 ///
+/// struct上的属性：
+///
+/// - `#[sql_helper(table = "t_user")]`：指定表名，默认为struct名字的snake_case
+/// - `#[sql_helper(history)]`：修改、删除前把数据写入`<table>_history`历史表
+/// - `#[sql_helper(hooks)]`：手动实现`sqlx_sqlhelper::SqlHelperHooks`，默认生成空实现
+/// - `#[sql_helper(new_record = "sentinel" | "option" | "exists" | "upsert")]`：`save_or_update`判断新增还是修改的方式
+/// - `#[sql_helper(has_many(Order, foreign_key = "user_id"), has_one(Profile))]`：一对多、一对一关联
+/// - `#[sql_helper(many_to_many(Role, through = "user_role"))]`：通过中间表的多对多关联
+/// - `#[index(name = "...", columns = [...], unique)]`：联合索引
+///
+/// 字段上的属性：
+///
+/// - `#[id]`：主键
+/// - `#[field_name("...")]`：表字段名
+/// - `#[create_time]`、`#[update_time]`：自动填充的时间字段
+/// - `#[created_by]`、`#[updated_by]`：通过`sqlx_sqlhelper::set_audit_context`自动填充的操作人
+/// - `#[version]`：乐观锁版本号
+/// - `#[validate(length(...), range(...), regex = "...", custom = "path::to::fn")]`：写操作前的校验
+/// - `#[column(type = "TEXT", precision = 12, scale = 4)]`：建表语句中的字段类型
+/// - `#[index]`、`#[unique]`：单字段索引，`#[unique]`还会生成`get_by_<字段>`、`exists_by_<字段>`
+/// - `#[belongs_to(User)]`：外键关联
 ///
 /// 需要在struct上下文中引入sqlx的db对象。
 ///
-/// ```
+/// ```ignore
 /// use super::db;
 /// ```
 ///
 /// # Examples
 ///
-/// ```
+/// ```ignore
 /// #[derive(SqlHelper)]
 /// pub struct Person {
 ///     #[id]
@@ -33,21 +68,42 @@ pub(crate) const DEFAULT_UPDATE_TIME_NAME: &str = "update_time";
 ///     pub create_time: NaiveDateTime,
 ///     #[update_time]
 ///     pub update_time: NaiveDateTime,
+///     #[created_by]
+///     pub created_by: Option<String>,
+///     #[updated_by]
+///     pub updated_by: Option<String>,
 /// }
 /// ```
-#[proc_macro_derive(SqlHelper, attributes(id, field_name, create_time, update_time))]
+#[proc_macro_derive(
+    SqlHelper,
+    attributes(
+        sql_helper,
+        id,
+        field_name,
+        create_time,
+        update_time,
+        created_by,
+        updated_by,
+        validate,
+        version,
+        column,
+        index,
+        unique,
+        belongs_to
+    )
+)]
 pub fn derive_sql_helper(input: TokenStream) -> TokenStream {
     let ast = parse_macro_input!(input as ItemStruct);
     sql_helper::impl_sql_helper(&ast)
 }
 
 /// 自动实现公用`id`、`create_time`、`update_time`的字段。
-/// 
+///
 /// 需要配合`SqlHelper`派生宏使用
-/// 
+///
 /// # Examples
 ///
-/// ```
+/// ```ignore
 /// #[common_fields]
 /// #[derive(SqlHelper)]
 /// pub struct Person {
@@ -61,3 +117,27 @@ pub fn common_fields(_attr: TokenStream, input: TokenStream) -> TokenStream {
     let mut ast = parse_macro_input!(input as ItemStruct);
     common_fields::impl_common_fields(&mut ast)
 }
+
+/// 只查询模型部分字段的投影结构体
+///
+/// `#[projection(of = User)]`指定投影的模型，每个字段都必须是模型中的字段，否则编译失败，
+/// 模型字段上`#[field_name]`指定的表字段名会通过`AS`映射到投影结构体的字段名。
+/// 通过模型的`list_as`、`page_as`方法查询。
+///
+/// # Examples
+///
+/// ```ignore
+/// #[derive(sqlx::FromRow, SqlProjection)]
+/// #[projection(of = User)]
+/// pub struct UserSummary {
+///     pub id: i32,
+///     pub account: String,
+/// }
+///
+/// let users = User::list_as::<UserSummary>("AND age > ?", args).await?;
+/// ```
+#[proc_macro_derive(SqlProjection, attributes(projection))]
+pub fn derive_sql_projection(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as ItemStruct);
+    projection::impl_sql_projection(&ast)
+}
@@ -0,0 +1,297 @@
+use proc_macro2::{Ident, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{Error, Field, Fields, Lit, Meta, NestedMeta, Result, Type};
+
+use crate::{
+    index::{get_index_meta_quote, Index},
+    sql_helper::{extract_type_from_option, field_attr_exists, get_table_field_name},
+    COLUMN_NAME, CREATED_BY_NAME, DEFAULT_CREATE_TIME_NAME, DEFAULT_UPDATE_TIME_NAME,
+    UPDATED_BY_NAME, VERSION_NAME,
+};
+
+/// 生成`TableMeta`常量的表达式
+pub fn get_table_meta_quote(
+    struct_name: &Ident,
+    table_name: &str,
+    fields: &Fields,
+    id: &Ident,
+    auto_increment: bool,
+    field_vec: &[&Field],
+    indexes: &[Index],
+) -> Result<TokenStream2> {
+    let struct_name_str = struct_name.to_string();
+    let id_name = id.to_string();
+    let id_column = match fields.iter().find(|field| field.ident.as_ref() == Some(id)) {
+        Some(field) => get_column_meta_quote(field, &id_name, true)?,
+        None => quote!(sqlx_sqlhelper::ColumnMeta {
+            name: #id_name,
+            field: #id_name,
+            rust_type: "i32",
+            sql_type_hint: "INT",
+            nullable: false,
+            is_id: true,
+        }),
+    };
+    let column_vec = field_vec
+        .iter()
+        .map(|field| get_column_meta_quote(field, &get_table_field_name(field), false))
+        .collect::<Result<Vec<_>>>()?;
+    let create_time = get_column_name_quote(fields, DEFAULT_CREATE_TIME_NAME, true);
+    let update_time = get_column_name_quote(fields, DEFAULT_UPDATE_TIME_NAME, true);
+    let created_by = get_column_name_quote(fields, CREATED_BY_NAME, false);
+    let updated_by = get_column_name_quote(fields, UPDATED_BY_NAME, false);
+    let version = get_column_name_quote(fields, VERSION_NAME, false);
+    let index_meta = get_index_meta_quote(indexes);
+    Ok(quote!(
+        sqlx_sqlhelper::TableMeta {
+            table: #table_name,
+            struct_name: #struct_name_str,
+            columns: &[#id_column, #(#column_vec),*],
+            id: #id_name,
+            auto_increment: #auto_increment,
+            create_time: #create_time,
+            update_time: #update_time,
+            created_by: #created_by,
+            updated_by: #updated_by,
+            version: #version,
+            indexes: #index_meta,
+        }
+    ))
+}
+
+fn get_column_meta_quote(field: &Field, name: &str, is_id: bool) -> Result<TokenStream2> {
+    let field_name = field
+        .ident
+        .as_ref()
+        .map(|ident| ident.to_string())
+        .unwrap_or_default();
+    let ty = &field.ty;
+    let rust_type = quote!(#ty).to_string().replace(' ', "");
+    //主键不能为`NULL`，`Option`主键只表示还没有插入数据库
+    let nullable = !is_id && extract_type_from_option(ty).is_some();
+    let sql_type_hint = get_column_type(field)?;
+    Ok(quote!(
+        sqlx_sqlhelper::ColumnMeta {
+            name: #name,
+            field: #field_name,
+            rust_type: #rust_type,
+            sql_type_hint: #sql_type_hint,
+            nullable: #nullable,
+            is_id: #is_id,
+        }
+    ))
+}
+
+/// 获取字段的MySQL类型
+///
+/// 优先使用`#[column(type = "TEXT")]`指定的类型，`Decimal`字段可以通过
+/// `#[column(precision = 12, scale = 4)]`指定精度，其余根据Rust类型推断
+fn get_column_type(field: &Field) -> Result<String> {
+    let ty = extract_type_from_option(&field.ty).unwrap_or(&field.ty);
+    let mut column_type = None;
+    let mut precision = None;
+    let mut scale = None;
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident(COLUMN_NAME))
+    {
+        let Meta::List(list) = attr.parse_meta()? else {
+            return Err(Error::new_spanned(attr, "expected #[column(...)]"));
+        };
+        for nested in list.nested {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("type") => {
+                    let Lit::Str(lit) = &nv.lit else {
+                        return Err(Error::new_spanned(nv.lit, "expected string literal"));
+                    };
+                    column_type = Some(lit.value());
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("precision") => {
+                    let Lit::Int(lit) = &nv.lit else {
+                        return Err(Error::new_spanned(nv.lit, "expected integer literal"));
+                    };
+                    precision = Some(lit.base10_parse::<u32>()?);
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("scale") => {
+                    let Lit::Int(lit) = &nv.lit else {
+                        return Err(Error::new_spanned(nv.lit, "expected integer literal"));
+                    };
+                    scale = Some(lit.base10_parse::<u32>()?);
+                }
+                other => return Err(Error::new_spanned(other, "unsupported attribute [column]")),
+            }
+        }
+    }
+    if let Some(column_type) = column_type {
+        return Ok(column_type);
+    }
+    let sql_type_hint = get_sql_type_hint(ty);
+    if precision.is_some() || scale.is_some() {
+        if !sql_type_hint.starts_with("DECIMAL") {
+            return Err(Error::new_spanned(
+                &field.ty,
+                "precision and scale only supports Decimal [column]",
+            ));
+        }
+        return Ok(format!(
+            "DECIMAL({},{})",
+            precision.unwrap_or(10),
+            scale.unwrap_or(2)
+        ));
+    }
+    Ok(sql_type_hint.to_string())
+}
+
+/// 获取带有指定属性的字段名，`by_name`为`true`时没有属性的同名字段也可以匹配
+fn get_column_name_quote(fields: &Fields, attr_name: &str, by_name: bool) -> TokenStream2 {
+    let field = fields
+        .iter()
+        .find(|field| field_attr_exists(field, attr_name))
+        .or_else(|| {
+            fields
+                .iter()
+                .filter(|_| by_name)
+                .find(|field| field.ident.as_ref().is_some_and(|ident| ident == attr_name))
+        });
+    match field {
+        Some(field) => {
+            let name = get_table_field_name(field);
+            quote!(Some(#name))
+        }
+        None => quote!(None),
+    }
+}
+
+/// 根据Rust类型推断MySQL字段类型，无法识别的类型默认为`VARCHAR(255)`
+pub(crate) fn get_sql_type_hint(ty: &Type) -> &'static str {
+    let Type::Path(type_path) = ty else {
+        return "VARCHAR(255)";
+    };
+    let Some(segment) = type_path.path.segments.last() else {
+        return "VARCHAR(255)";
+    };
+    match segment.ident.to_string().as_str() {
+        "bool" => "TINYINT(1)",
+        "i8" => "TINYINT",
+        "u8" => "TINYINT UNSIGNED",
+        "i16" => "SMALLINT",
+        "u16" => "SMALLINT UNSIGNED",
+        "i32" => "INT",
+        "u32" => "INT UNSIGNED",
+        "i64" => "BIGINT",
+        "u64" => "BIGINT UNSIGNED",
+        "f32" => "FLOAT",
+        "f64" => "DOUBLE",
+        "NaiveDateTime" => "DATETIME",
+        "NaiveDate" => "DATE",
+        "NaiveTime" => "TIME",
+        "DateTime" => "TIMESTAMP",
+        "Decimal" | "BigDecimal" => "DECIMAL(10,2)",
+        "Vec" => "BLOB",
+        "Json" | "Value" => "JSON",
+        "Uuid" => "BINARY(16)",
+        _ => "VARCHAR(255)",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::{parse_quote, ItemStruct};
+
+    use super::{get_column_meta_quote, get_column_type};
+
+    fn column_types(item: ItemStruct) -> Vec<String> {
+        item.fields
+            .iter()
+            .map(|field| get_column_type(field).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn type_mapping() {
+        let item: ItemStruct = parse_quote!(
+            struct User {
+                id: i64,
+                age: i32,
+                level: u8,
+                enabled: bool,
+                score: f64,
+                name: String,
+                nick: Option<String>,
+                balance: Option<rust_decimal::Decimal>,
+                token: uuid::Uuid,
+                birthday: chrono::NaiveDate,
+                create_time: NaiveDateTime,
+                avatar: Vec<u8>,
+            }
+        );
+        assert_eq!(
+            column_types(item),
+            [
+                "BIGINT",
+                "INT",
+                "TINYINT UNSIGNED",
+                "TINYINT(1)",
+                "DOUBLE",
+                "VARCHAR(255)",
+                "VARCHAR(255)",
+                "DECIMAL(10,2)",
+                "BINARY(16)",
+                "DATE",
+                "DATETIME",
+                "BLOB",
+            ]
+        );
+    }
+
+    #[test]
+    fn nullable() {
+        let item: ItemStruct = parse_quote!(
+            struct User {
+                id: Option<i64>,
+                name: String,
+                nick: Option<String>,
+            }
+        );
+        let nullable = |index: usize, is_id: bool| {
+            let field = item.fields.iter().nth(index).unwrap();
+            get_column_meta_quote(field, "c", is_id)
+                .unwrap()
+                .to_string()
+                .contains("nullable : true")
+        };
+        //`Option`主键只表示还没有插入数据库，不能为`NULL`
+        assert!(!nullable(0, true));
+        assert!(!nullable(1, false));
+        assert!(nullable(2, false));
+    }
+
+    #[test]
+    fn column_attr() {
+        let item: ItemStruct = parse_quote!(
+            struct Product {
+                #[column(type = "TEXT")]
+                description: Option<String>,
+                #[column(precision = 12, scale = 4)]
+                price: Decimal,
+                #[column(scale = 0)]
+                amount: Decimal,
+            }
+        );
+        assert_eq!(
+            column_types(item),
+            ["TEXT", "DECIMAL(12,4)", "DECIMAL(10,0)"]
+        );
+
+        //只有`Decimal`支持精度
+        let item: ItemStruct = parse_quote!(
+            struct Product {
+                #[column(precision = 12)]
+                price: f64,
+            }
+        );
+        let field = item.fields.iter().next().unwrap();
+        assert!(get_column_type(field).is_err());
+    }
+}
@@ -0,0 +1,170 @@
+use syn::{Attribute, Error, Lit, Meta, MetaList, NestedMeta, Path, Result};
+
+const SQL_HELPER_ATTR: &str = "sql_helper";
+
+/// struct上`#[sql_helper(...)]`属性的配置
+#[derive(Default)]
+pub struct SqlHelperOptions {
+    /// 修改、删除时是否把旧数据写入`<table>_history`历史表
+    pub history: bool,
+    /// 是否由用户手动实现`SqlHelperHooks`，否则自动生成空实现
+    pub hooks: bool,
+    /// 表名，默认为struct名字的snake_case
+    pub table: Option<String>,
+    /// `has_many(Order, foreign_key = "user_id")`
+    pub has_many: Vec<Relation>,
+    /// `has_one(Profile, foreign_key = "user_id")`
+    pub has_one: Vec<Relation>,
+    /// `many_to_many(Role, through = "user_role", foreign_key = "user_id", related_key = "role_id")`
+    pub many_to_many: Vec<Relation>,
+    /// `save_or_update`判断新数据的方式，默认根据主键类型推断
+    pub new_record: Option<NewRecord>,
+}
+
+/// `#[sql_helper(new_record = "...")]`
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum NewRecord {
+    /// `sentinel`：整数自增主键，主键大于0时修改，否则新增
+    Sentinel,
+    /// `option`：`Option`自增主键，主键为`Some`时修改，`None`时新增
+    Option,
+    /// `exists`：主键由调用方赋值，根据数据库中是否存在该主键判断
+    Exists,
+    /// `upsert`：主键由调用方赋值，通过`INSERT ... ON DUPLICATE KEY UPDATE`新增或修改
+    Upsert,
+}
+
+impl NewRecord {
+    fn from_lit(lit: &Lit) -> Result<Self> {
+        match lit_str(lit)?.as_str() {
+            "sentinel" => Ok(Self::Sentinel),
+            "option" => Ok(Self::Option),
+            "exists" => Ok(Self::Exists),
+            "upsert" => Ok(Self::Upsert),
+            _ => Err(Error::new_spanned(
+                lit,
+                "expected one of `sentinel`, `option`, `exists`, `upsert` [sql_helper]",
+            )),
+        }
+    }
+}
+
+/// struct上声明的一对多、一对一、多对多关联
+pub struct Relation {
+    /// 关联的类型
+    pub target: Path,
+    /// 关联表中的外键字段名，默认为`<struct名字的snake_case>_id`
+    pub foreign_key: Option<String>,
+    /// 生成的方法名，默认为关联类型名字的snake_case，`has_many`为复数形式
+    pub name: Option<String>,
+    /// 多对多关联的中间表
+    pub through: Option<String>,
+    /// 多对多关联中间表中关联类型的外键字段名，默认为`<关联类型名字的snake_case>_id`
+    pub related_key: Option<String>,
+}
+
+impl Relation {
+    fn from_list(list: MetaList, many_to_many: bool) -> Result<Self> {
+        let relation_path = list.path.clone();
+        let mut nested = list.nested.into_iter();
+        let Some(NestedMeta::Meta(Meta::Path(target))) = nested.next() else {
+            return Err(Error::new_spanned(
+                list.path,
+                "expected related type, e.g. has_many(Order) [sql_helper]",
+            ));
+        };
+        let mut relation = Self {
+            target,
+            foreign_key: None,
+            name: None,
+            through: None,
+            related_key: None,
+        };
+        for meta in nested {
+            match meta {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("foreign_key") => {
+                    relation.foreign_key = Some(lit_str(&nv.lit)?);
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("name") => {
+                    relation.name = Some(lit_str(&nv.lit)?);
+                }
+                NestedMeta::Meta(Meta::NameValue(nv))
+                    if many_to_many && nv.path.is_ident("through") =>
+                {
+                    relation.through = Some(lit_str(&nv.lit)?);
+                }
+                NestedMeta::Meta(Meta::NameValue(nv))
+                    if many_to_many && nv.path.is_ident("related_key") =>
+                {
+                    relation.related_key = Some(lit_str(&nv.lit)?);
+                }
+                other => {
+                    return Err(Error::new_spanned(
+                        other,
+                        "unsupported attribute [sql_helper]",
+                    ))
+                }
+            }
+        }
+        if many_to_many && relation.through.is_none() {
+            return Err(Error::new_spanned(
+                relation_path,
+                "expected `through = \"...\"` [sql_helper]",
+            ));
+        }
+        Ok(relation)
+    }
+}
+
+fn lit_str(lit: &Lit) -> Result<String> {
+    match lit {
+        Lit::Str(lit) => Ok(lit.value()),
+        _ => Err(Error::new_spanned(lit, "expected string literal")),
+    }
+}
+
+impl SqlHelperOptions {
+    pub fn from_attrs(attrs: &[Attribute]) -> Result<Self> {
+        let mut options = Self::default();
+        for attr in attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident(SQL_HELPER_ATTR))
+        {
+            let Meta::List(list) = attr.parse_meta()? else {
+                return Err(Error::new_spanned(attr, "expected #[sql_helper(...)]"));
+            };
+            for nested in list.nested {
+                match nested {
+                    NestedMeta::Meta(Meta::Path(path)) if path.is_ident("history") => {
+                        options.history = true
+                    }
+                    NestedMeta::Meta(Meta::Path(path)) if path.is_ident("hooks") => {
+                        options.hooks = true
+                    }
+                    NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("table") => {
+                        options.table = Some(lit_str(&nv.lit)?);
+                    }
+                    NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("new_record") => {
+                        options.new_record = Some(NewRecord::from_lit(&nv.lit)?);
+                    }
+                    NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("has_many") => {
+                        options.has_many.push(Relation::from_list(list, false)?)
+                    }
+                    NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("has_one") => {
+                        options.has_one.push(Relation::from_list(list, false)?)
+                    }
+                    NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("many_to_many") => {
+                        options.many_to_many.push(Relation::from_list(list, true)?)
+                    }
+                    other => {
+                        return Err(Error::new_spanned(
+                            other,
+                            "unsupported attribute [sql_helper]",
+                        ))
+                    }
+                }
+            }
+        }
+        Ok(options)
+    }
+}
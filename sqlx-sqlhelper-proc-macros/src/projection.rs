@@ -0,0 +1,62 @@
+use proc_macro::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::{spanned::Spanned, Attribute, Error, Fields, Ident, ItemStruct, Path, Result, Token};
+
+const PROJECTION_ATTR: &str = "projection";
+
+/// 解析`#[projection(of = User)]`
+fn get_projection_of(attrs: &[Attribute]) -> Result<Option<Path>> {
+    let Some(attr) = attrs
+        .iter()
+        .find(|attr| attr.path.is_ident(PROJECTION_ATTR))
+    else {
+        return Ok(None);
+    };
+    attr.parse_args_with(|input: syn::parse::ParseStream| {
+        let name: Ident = input.parse()?;
+        if name != "of" {
+            return Err(Error::new(name.span(), "expected `of = ...` [projection]"));
+        }
+        input.parse::<Token![=]>()?;
+        input.parse::<Path>()
+    })
+    .map(Some)
+}
+
+pub fn impl_sql_projection(ast: &ItemStruct) -> TokenStream {
+    let struct_name = &ast.ident;
+    let of = match get_projection_of(&ast.attrs) {
+        Ok(Some(of)) => of,
+        Ok(None) => {
+            return Error::new_spanned(struct_name, "expected #[projection(of = ...)]")
+                .into_compile_error()
+                .into()
+        }
+        Err(e) => return e.into_compile_error().into(),
+    };
+    let Fields::Named(fields) = &ast.fields else {
+        return Error::new_spanned(struct_name, "expected named fields [projection]")
+            .into_compile_error()
+            .into();
+    };
+
+    //每个字段都必须是模型的字段，表字段名取模型上`#[field_name]`指定的名字
+    let field_vec = fields.named.iter().filter_map(|field| {
+        let field_name = field.ident.as_ref()?.to_string();
+        Some(quote_spanned!(field.span()=>
+            (<#of as sqlx_sqlhelper::Crud>::META.field_column(#field_name), #field_name)
+        ))
+    });
+
+    quote!(
+        impl sqlx_sqlhelper::Projection for #struct_name {
+            type Of = #of;
+
+            const FIELDS: &'static [(&'static str, &'static str)] = &[#(#field_vec),*];
+        }
+
+        //关联常量只有在使用时才会求值，这里强制在编译时检查
+        const _: &[(&str, &str)] = <#struct_name as sqlx_sqlhelper::Projection>::FIELDS;
+    )
+    .into()
+}
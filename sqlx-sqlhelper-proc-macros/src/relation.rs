@@ -0,0 +1,259 @@
+use inflector::Inflector;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse::{Parse, ParseStream},
+    Error, Field, Ident, LitStr, Path, Result, Token,
+};
+
+use crate::{
+    options::SqlHelperOptions,
+    sql_helper::{extract_type_from_option, get_field_attr},
+    BELONGS_TO_NAME,
+};
+
+/// `#[belongs_to(User)]`、`#[belongs_to(User, name = "author")]`
+struct BelongsTo {
+    target: Path,
+    name: Option<LitStr>,
+}
+
+impl Parse for BelongsTo {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let target = input.parse()?;
+        let mut name = None;
+        while input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            let ident: Ident = input.parse()?;
+            if ident != "name" {
+                return Err(Error::new(
+                    ident.span(),
+                    format!("unsupported argument `{}` [belongs_to]", ident),
+                ));
+            }
+            input.parse::<Token![=]>()?;
+            name = Some(input.parse()?);
+        }
+        Ok(Self { target, name })
+    }
+}
+
+/// 关联对象的名字，默认为类型名字的snake_case
+fn relation_name(target: &Path, name: Option<&LitStr>) -> String {
+    match name {
+        Some(name) => name.value(),
+        None => target
+            .segments
+            .last()
+            .map(|segment| segment.ident.to_string().to_snake_case())
+            .unwrap_or_default(),
+    }
+}
+
+/// 简单的复数形式，用于生成`load_users`、`orders`等方法名
+pub(crate) fn pluralize(name: &str) -> String {
+    if let Some(stem) = name.strip_suffix('y') {
+        if !stem.ends_with(['a', 'e', 'i', 'o', 'u']) {
+            return format!("{}ies", stem);
+        }
+    }
+    if name.ends_with('s') || name.ends_with('x') || name.ends_with("ch") || name.ends_with("sh") {
+        return format!("{}es", name);
+    }
+    format!("{}s", name)
+}
+
+/// 生成字段上`#[belongs_to(...)]`的关联方法
+///
+/// `#[belongs_to(User)] pub user_id: i32`会生成`user`和`load_users`方法，
+/// 外键为`Option`时`user`返回`Option<User>`
+pub fn get_belongs_to_fn(fields: &[&Field], pool: &TokenStream2) -> Result<TokenStream2> {
+    let error = quote!(sqlx_sqlhelper::Error);
+    let mut fn_vec = Vec::new();
+    for field in fields {
+        let Some((_, attr)) = get_field_attr(field, BELONGS_TO_NAME) else {
+            continue;
+        };
+        let Some(field_ident) = &field.ident else {
+            continue;
+        };
+        let belongs_to = attr.parse_args::<BelongsTo>()?;
+        let target = &belongs_to.target;
+        let name = relation_name(target, belongs_to.name.as_ref());
+        let fn_name = format_ident!("{}", name);
+        let load_fn_name = format_ident!("load_{}", pluralize(&name));
+        let doc = format!(
+            "批量获取`{}`关联的数据，通过`IN (...)`查询，超过1000个时分批查询，返回以`{}`为key的`HashMap`",
+            field_ident, field_ident
+        );
+        let (get_fn, key_ty, ids_quote) = match extract_type_from_option(&field.ty) {
+            Some(key_ty) => (
+                quote!(
+                    /// 外键为`None`或者数据不存在时返回`None`
+                    pub async fn #fn_name(&self) -> Result<Option<#target>, #error> {
+                        match self.#field_ident.clone() {
+                            Some(id) => #target::find_by_id(id).await,
+                            None => Ok(None),
+                        }
+                    }
+                ),
+                key_ty,
+                quote!(rows.iter().filter_map(|row| row.#field_ident.clone())),
+            ),
+            None => (
+                quote!(
+                    /// 数据不存在时返回`Error::NotFound`
+                    pub async fn #fn_name(&self) -> Result<#target, #error> {
+                        #target::get_by_id(self.#field_ident.clone()).await
+                    }
+                ),
+                &field.ty,
+                quote!(rows.iter().map(|row| row.#field_ident.clone())),
+            ),
+        };
+        fn_vec.push(quote!(
+            #get_fn
+
+            #[doc = #doc]
+            pub async fn #load_fn_name(
+                rows: &[Self],
+            ) -> Result<std::collections::HashMap<#key_ty, #target>, #error> {
+                let ids = #ids_quote.collect::<Vec<_>>();
+                sqlx_sqlhelper::load_by_ids::<#target>(#pool, &ids).await
+            }
+        ));
+    }
+    Ok(quote!(#(#fn_vec)*))
+}
+
+/// 生成struct上`#[sql_helper(has_many(...), has_one(...), many_to_many(...))]`的关联方法
+///
+/// `has_many(Order)`生成`orders`和`with_orders`，`has_one(Profile)`生成`profile`和`with_profile`，
+/// 预加载方法只执行一次`IN (...)`查询。
+/// `many_to_many(Role, through = "user_role")`生成`roles`、`attach_role`、`detach_role`和`sync_roles`
+pub fn get_has_relation_fn(
+    options: &SqlHelperOptions,
+    struct_name: &Ident,
+    id: &Ident,
+    pool: &TokenStream2,
+) -> TokenStream2 {
+    let error = quote!(sqlx_sqlhelper::Error);
+    let default_foreign_key = format!("{}_id", struct_name.to_string().to_snake_case());
+    let has_many_fn_vec = options.has_many.iter().map(|relation| {
+        let target = &relation.target;
+        let foreign_key = relation.foreign_key.as_ref().unwrap_or(&default_foreign_key);
+        let name = match &relation.name {
+            Some(name) => name.clone(),
+            None => pluralize(&relation_name(target, None)),
+        };
+        let fn_name = format_ident!("{}", name);
+        let with_fn_name = format_ident!("with_{}", name);
+        quote!(
+            pub async fn #fn_name(&self) -> Result<Vec<#target>, #error> {
+                sqlx_sqlhelper::list_by_foreign_key::<#target, _>(#pool, #foreign_key, self.#id.clone()).await
+            }
+
+            /// 预加载关联数据，只执行一次`IN (...)`查询
+            pub async fn #with_fn_name(rows: Vec<Self>) -> Result<Vec<(Self, Vec<#target>)>, #error> {
+                let keys = rows.iter().map(|row| row.#id.clone()).collect::<Vec<_>>();
+                let mut map = sqlx_sqlhelper::load_by_foreign_key::<#target, _>(#pool, #foreign_key, &keys).await?;
+                Ok(rows
+                    .into_iter()
+                    .map(|row| {
+                        let children = map.remove(&row.#id).unwrap_or_default();
+                        (row, children)
+                    })
+                    .collect())
+            }
+        )
+    });
+    let has_one_fn_vec = options.has_one.iter().map(|relation| {
+        let target = &relation.target;
+        let foreign_key = relation.foreign_key.as_ref().unwrap_or(&default_foreign_key);
+        let name = relation_name(target, None);
+        let name = relation.name.as_ref().unwrap_or(&name);
+        let fn_name = format_ident!("{}", name);
+        let with_fn_name = format_ident!("with_{}", name);
+        quote!(
+            /// 没有关联数据时返回`None`，有多条时返回主键最小的一条
+            pub async fn #fn_name(&self) -> Result<Option<#target>, #error> {
+                sqlx_sqlhelper::get_by_foreign_key::<#target, _>(#pool, #foreign_key, self.#id.clone()).await
+            }
+
+            /// 预加载关联数据，只执行一次`IN (...)`查询
+            pub async fn #with_fn_name(rows: Vec<Self>) -> Result<Vec<(Self, Option<#target>)>, #error> {
+                let keys = rows.iter().map(|row| row.#id.clone()).collect::<Vec<_>>();
+                let mut map = sqlx_sqlhelper::load_one_by_foreign_key::<#target, _>(#pool, #foreign_key, &keys).await?;
+                Ok(rows
+                    .into_iter()
+                    .map(|row| {
+                        let child = map.remove(&row.#id);
+                        (row, child)
+                    })
+                    .collect())
+            }
+        )
+    });
+    let many_to_many_fn_vec = options.many_to_many.iter().map(|relation| {
+        let target = &relation.target;
+        let foreign_key = relation.foreign_key.as_ref().unwrap_or(&default_foreign_key);
+        let through = relation.through.as_deref().unwrap_or_default();
+        let name = relation_name(target, None);
+        let name = relation.name.as_ref().unwrap_or(&name);
+        let default_related_key = format!("{}_id", relation_name(target, None));
+        let related_key = relation.related_key.as_ref().unwrap_or(&default_related_key);
+        let fn_name = format_ident!("{}", pluralize(name));
+        let attach_fn_name = format_ident!("attach_{}", name);
+        let detach_fn_name = format_ident!("detach_{}", name);
+        let sync_fn_name = format_ident!("sync_{}", pluralize(name));
+        let related_id_ty = quote!(<#target as sqlx_sqlhelper::Crud>::Id);
+        let sync_doc = format!(
+            "在一个事务中比对`{}`中已有的关联，只删除多余的关联并插入缺少的关联",
+            through
+        );
+        quote!(
+            pub async fn #fn_name(&self) -> Result<Vec<#target>, #error> {
+                sqlx_sqlhelper::list_through::<#target, _>(#pool, #through, #foreign_key, #related_key, self.#id.clone()).await
+            }
+
+            /// 添加关联，关联已经存在时返回`false`
+            pub async fn #attach_fn_name(&self, id: #related_id_ty) -> Result<bool, #error> {
+                sqlx_sqlhelper::attach_through(#pool, #through, #foreign_key, #related_key, self.#id.clone(), id).await
+            }
+
+            /// 删除关联，关联不存在时返回`false`
+            pub async fn #detach_fn_name(&self, id: #related_id_ty) -> Result<bool, #error> {
+                sqlx_sqlhelper::detach_through(#pool, #through, #foreign_key, #related_key, self.#id.clone(), id).await
+            }
+
+            #[doc = #sync_doc]
+            pub async fn #sync_fn_name(
+                &self,
+                ids: &[#related_id_ty],
+            ) -> Result<sqlx_sqlhelper::SyncChanges<#related_id_ty>, #error> {
+                sqlx_sqlhelper::sync_through(#pool, #through, #foreign_key, #related_key, self.#id.clone(), ids).await
+            }
+        )
+    });
+    quote!(
+        #(#has_many_fn_vec)*
+        #(#has_one_fn_vec)*
+        #(#many_to_many_fn_vec)*
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::pluralize;
+
+    #[test]
+    fn pluralize_names() {
+        assert_eq!(pluralize("user"), "users");
+        assert_eq!(pluralize("order_item"), "order_items");
+        assert_eq!(pluralize("category"), "categories");
+        assert_eq!(pluralize("key"), "keys");
+        assert_eq!(pluralize("address"), "addresses");
+        assert_eq!(pluralize("box"), "boxes");
+        assert_eq!(pluralize("branch"), "branches");
+        assert_eq!(pluralize("wish"), "wishes");
+    }
+}
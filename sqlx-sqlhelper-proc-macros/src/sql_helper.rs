@@ -4,10 +4,22 @@ use proc_macro2::{Ident, TokenStream as TokenStream2};
 use quote::{format_ident, quote};
 use syn::{Attribute, Field, Fields, ItemStruct, Visibility};
 
-use crate::{DEFAULT_CREATE_TIME_NAME, DEFAULT_ID_NAME, DEFAULT_UPDATE_TIME_NAME};
+use crate::{
+    index::get_indexes,
+    meta::get_table_meta_quote,
+    options::{NewRecord, SqlHelperOptions},
+    relation::{get_belongs_to_fn, get_has_relation_fn},
+    validate::get_validate_fn,
+    CREATED_BY_NAME, DEFAULT_CREATE_TIME_NAME, DEFAULT_ID_NAME, DEFAULT_UPDATE_TIME_NAME,
+    UNIQUE_NAME, UPDATED_BY_NAME, VERSION_NAME,
+};
 
 pub fn impl_sql_helper(ast: &ItemStruct) -> TokenStream {
     //初始化model，默认model实现，分页model实现等。初始化获取一个model
+    let options = match SqlHelperOptions::from_attrs(&ast.attrs) {
+        Ok(options) => options,
+        Err(e) => return e.into_compile_error().into(),
+    };
 
     let mut field_vec = Vec::new();
     ast.fields.iter().for_each(|field| {
@@ -31,29 +43,67 @@ pub fn impl_sql_helper(ast: &ItemStruct) -> TokenStream {
         //.map(get_table_field_name)
         .collect::<Vec<_>>();
 
+    let validate_fn = match get_validate_fn(&field_vec) {
+        Ok(validate_fn) => validate_fn,
+        Err(e) => return e.into_compile_error().into(),
+    };
+
     let struct_name = &ast.ident;
     let self_ident = format_ident!("self");
     //let varname = format_ident!("_{}", ident);
     let struct_var_name = format_ident!("{}", struct_name.to_string().to_snake_case());
-    let table_name = format!("`{}`", struct_var_name);
+    let table_name_str = options
+        .table
+        .clone()
+        .unwrap_or_else(|| struct_var_name.to_string());
+    let table_name = format!("`{}`", table_name_str);
     let id = get_ident(&ast.fields, DEFAULT_ID_NAME);
-    let create_time = get_ident(&ast.fields, DEFAULT_CREATE_TIME_NAME);
-    let update_time = get_ident(&ast.fields, DEFAULT_UPDATE_TIME_NAME);
+    let id_ty = get_id_type(&ast.fields, &id);
+    let new_record = match get_new_record(options.new_record, &id_ty) {
+        Ok(new_record) => new_record,
+        Err(e) => return e.into_compile_error().into(),
+    };
+    //自增主键插入时不包含主键字段，插入后通过`last_insert_id`赋值
+    let auto_increment = matches!(new_record, NewRecord::Sentinel | NewRecord::Option);
+    let create_time_field = get_time_field(&ast.fields, DEFAULT_CREATE_TIME_NAME);
+    let update_time_field = get_time_field(&ast.fields, DEFAULT_UPDATE_TIME_NAME);
+    let create_time = create_time_field.and_then(|field| field.ident.clone());
+    let created_by = get_attr_field(&ast.fields, CREATED_BY_NAME);
+    let updated_by = get_attr_field(&ast.fields, UPDATED_BY_NAME);
     let pool = quote!(&*db::POOL);
     let query = quote!(sqlx::query);
     let query_as = quote!(sqlx::query_as::<_, Self>);
+    let hooks = quote!(sqlx_sqlhelper::SqlHelperHooks);
+    let error = quote!(sqlx_sqlhelper::Error);
+
+    //主键和所有表字段，第一个为主键
+    let column_field_vec = ast
+        .fields
+        .iter()
+        .filter(|field| field.ident.as_ref() == Some(&id))
+        .chain(field_vec.iter().copied())
+        .collect::<Vec<_>>();
+    let indexes = match get_indexes(&table_name_str, &ast.attrs, &column_field_vec) {
+        Ok(indexes) => indexes,
+        Err(e) => return e.into_compile_error().into(),
+    };
+
+    let belongs_to_fn = match get_belongs_to_fn(&field_vec, &pool) {
+        Ok(belongs_to_fn) => belongs_to_fn,
+        Err(e) => return e.into_compile_error().into(),
+    };
 
-    let select_base_sql = format!(
-        "SELECT {}, {} FROM {}",
+    let select_field_sql = format!(
+        "{}, {}",
         field_to_sql_quote(&id.to_string()),
         table_field_name_vec
             .iter()
             .map(|field| field_to_sql_quote(field))
             .collect::<Vec<_>>()
             .join(", ")
-            .trim_end(),
-        table_name
+            .trim_end()
     );
+    let select_base_sql = format!("SELECT {} FROM {}", select_field_sql, table_name);
     let count_base_sql = format!("SELECT count(1) FROM {}", table_name);
 
     //查找函数
@@ -62,72 +112,103 @@ pub fn impl_sql_helper(ast: &ItemStruct) -> TokenStream {
         select_base_sql,
         field_to_sql_quote(&id.to_string())
     );
-    let get_by_id_fn = quote!(
-        pub async fn get_by_id(#id: i32) -> Result<Self, sqlx::Error> {
-            //sqlx::query_as::<_, Self>(&format!(
-            //    "SELECT * FROM {} WHERE id = ?",
-            //    stringify!(#struct_name)
-            //))
-            #query_as(#get_by_id_sql)
-            .bind(#id)
-            .fetch_one(#pool)
-            .await
-        }
-    );
-
-    //列表函数
-    let list_fn = quote!(
-        pub async fn list() -> Result<Vec<Self>, sqlx::Error> {
-            #query_as(#select_base_sql)
-            .fetch_all(#pool)
-            .await
-        }
-    );
-
     //删除函数
     let delete_sql = format!(
         "DELETE FROM {} WHERE {} = ?",
         table_name,
         field_to_sql_quote(&id.to_string())
     );
+    let delete_history_op = quote!(sqlx_sqlhelper::HistoryOp::Delete);
+    let delete_body = get_execute_quote(
+        &options,
+        &pool,
+        quote!(self.#id),
+        &delete_history_op,
+        quote!(#query(#delete_sql).bind(self.#id.clone())),
+    );
     let delete_fn = quote!(
-        pub async fn delete(&self) -> Result<bool, sqlx::Error> {
-            #query(#delete_sql)
-            .bind(self.#id)
-            .execute(#pool)
-            .await
-            .map(|f| f.rows_affected() > 0)
+        pub async fn delete(&self) -> Result<bool, #error> {
+            #hooks::before_delete(self).await?;
+            let rows_affected = { #delete_body };
+            #hooks::after_delete(self).await?;
+            Ok(rows_affected > 0)
         }
     );
 
-    let delete_by_id_fn = quote!(
-        pub async fn delete_by_id(#id: i32) -> Result<bool, sqlx::Error> {
-            #query(#delete_sql)
-            .bind(#id)
-            .execute(#pool)
-            .await
-            .map(|f| f.rows_affected() > 0)
-        }
+    let delete_by_id_body = get_execute_quote(
+        &options,
+        &pool,
+        quote!(#id),
+        &delete_history_op,
+        quote!(#query(#delete_sql).bind(#id)),
     );
+    //自定义钩子时需要先查询出数据，再调用`delete`触发钩子
+    let delete_by_id_fn = if options.hooks {
+        quote!(
+            pub async fn delete_by_id(#id: #id_ty) -> Result<bool, #error> {
+                match Self::get_by_id(#id).await {
+                    Ok(row) => row.delete().await,
+                    Err(#error::NotFound) => Ok(false),
+                    Err(e) => Err(e),
+                }
+            }
+        )
+    } else {
+        quote!(
+            pub async fn delete_by_id(#id: #id_ty) -> Result<bool, #error> {
+                let rows_affected = { #delete_by_id_body };
+                Ok(rows_affected > 0)
+            }
+        )
+    };
 
-    //新增函数
+    //新增函数，主键由调用方赋值时插入主键字段
+    let insert_column_vec = match auto_increment {
+        true => table_field_name_vec.clone(),
+        false => std::iter::once(id.to_string())
+            .chain(table_field_name_vec.iter().cloned())
+            .collect(),
+    };
     let insert_sql = format!(
         "INSERT INTO {} ({}) VALUES({})",
         table_name,
-        table_field_name_vec
+        insert_column_vec
             .iter()
             .map(|field| field_to_sql_quote(field))
             .collect::<Vec<_>>()
             .join(", ")
             .trim_end(),
         "?, "
-            .repeat(field_vec.len())
+            .repeat(insert_column_vec.len())
             .trim_end()
             .trim_end_matches(',')
     );
 
-    let insert_bind_quote_vec = fields_to_bind_quote(&self_ident, &field_vec);
-    let insert_auto_time_quote = get_auto_time_quote(&self_ident, Some(&create_time), &update_time);
+    let mut insert_bind_quote_vec = fields_to_bind_quote(&self_ident, &field_vec);
+    if !auto_increment {
+        insert_bind_quote_vec.insert(0, quote!(.bind(&self.#id)));
+    }
+    let get_insert_execute_quote = |executor: TokenStream2| {
+        let execute_quote = quote!(
+            #query(sql)
+            #(#insert_bind_quote_vec)*
+            .execute(#executor)
+            .await?
+        );
+        match new_record {
+            NewRecord::Sentinel => quote!(self.#id = #execute_quote.last_insert_id() as #id_ty;),
+            NewRecord::Option => {
+                let id_inner_ty = extract_type_from_option(&id_ty);
+                quote!(self.#id = Some(#execute_quote.last_insert_id() as #id_inner_ty);)
+            }
+            NewRecord::Exists | NewRecord::Upsert => quote!(#execute_quote;),
+        }
+    };
+    let insert_execute_quote = get_insert_execute_quote(pool.clone());
+    let tran_insert_execute_quote = get_insert_execute_quote(quote!(&mut **tran));
+    let insert_auto_time_quote =
+        get_auto_time_quote(&self_ident, create_time_field, update_time_field);
+    let insert_audit_quote = get_audit_quote(&self_ident, created_by, updated_by);
     //pub async fn add(#struct_var_name:&Self) -> Result<Self, sqlx::Error> {
     //    let sql = #add_sql;
     //    let last_id = #query(sql)
@@ -135,90 +216,535 @@ pub fn impl_sql_helper(ast: &ItemStruct) -> TokenStream {
     //    .execute(#pool).await?.last_insert_id();
     //    Self::find(last_id as i32).await
     //}
+    //MariaDB 10.5+支持`INSERT ... RETURNING`，一条语句返回完整的数据
+    let insert_returning_sql = format!("{} RETURNING {}", insert_sql, select_field_sql);
     let insert_fn = quote!(
-        pub async fn insert(&mut self) -> Result<Self, sqlx::Error> {
+        /// 插入数据后通过`get_by_id`重新查询完整的数据，不需要重新查询时使用`insert_returning_id`
+        pub async fn insert(&mut self) -> Result<Self, #error> {
+            self.insert_returning_id().await?;
+            Self::get_by_id(self.#id.clone()).await
+        }
+
+        /// 插入数据，只把自增主键赋值给`self`，不会重新查询
+        pub async fn insert_returning_id(&mut self) -> Result<#id_ty, #error> {
+            #hooks::before_insert(self).await?;
+            self.validate()?;
             let sql = #insert_sql;
-            let last_id = #query(sql)
+            #insert_execute_quote
+            #hooks::after_insert(self).await?;
+            Ok(self.#id.clone())
+        }
+
+        /// 通过`INSERT ... RETURNING`在一条语句中插入并返回完整的数据，包括数据库生成的默认值
+        ///
+        /// 只支持MariaDB 10.5及以上版本，MySQL不支持`RETURNING`
+        pub async fn insert_returning(&mut self) -> Result<Self, #error> {
+            #hooks::before_insert(self).await?;
+            self.validate()?;
+            let sql = #insert_returning_sql;
+            let row = #query_as(sql)
             #(#insert_bind_quote_vec)*
-            .execute(#pool).await?.last_insert_id();
-            self.#id = last_id as i32;
-            Self::get_by_id(self.#id).await
+            .fetch_one(#pool).await?;
+            self.#id = row.#id.clone();
+            #hooks::after_insert(self).await?;
+            Ok(row)
         }
 
         /// 如果定义的`create_time`，`update_time`字段是`Default::default()`默认值，则更新为当前时间
         ///
         /// `Default::default()`一般为`1970-01-01T00:00:00`等
-        pub async fn insert_auto_time(&mut self) -> Result<Self, sqlx::Error> {
+        ///
+        /// 如果定义了`created_by`，`updated_by`字段，则从审计上下文中获取当前操作人
+        pub async fn insert_auto_time(&mut self) -> Result<Self, #error> {
             #insert_auto_time_quote
+            #insert_audit_quote
             self.insert().await
         }
 
+        /// 与`insert_auto_time`相同，但是不会重新查询，参考`insert_returning_id`
+        pub async fn insert_auto_time_returning_id(&mut self) -> Result<#id_ty, #error> {
+            #insert_auto_time_quote
+            #insert_audit_quote
+            self.insert_returning_id().await
+        }
+
     );
 
-    //更新函数
+    //更新函数，定义了`#[version]`字段时使用乐观锁，版本号由数据库自增
+    let version = get_attr_field(&ast.fields, VERSION_NAME);
+    let version_field_name = version.map(get_table_field_name);
     let update_sql = format!(
-        "UPDATE {} SET {} WHERE {} = ?",
+        "UPDATE {} SET {} WHERE {} = ?{}",
         table_name,
         table_field_name_vec
             .iter()
-            .map(|field_str| format!("{} = ?", field_to_sql_quote(field_str)))
+            .map(
+                |field_str| match Some(field_str) == version_field_name.as_ref() {
+                    true => format!(
+                        "{} = {} + 1",
+                        field_to_sql_quote(field_str),
+                        field_to_sql_quote(field_str)
+                    ),
+                    false => format!("{} = ?", field_to_sql_quote(field_str)),
+                }
+            )
             .collect::<Vec<_>>()
             .join(", "),
+        field_to_sql_quote(&id.to_string()),
+        version_field_name
+            .as_ref()
+            .map(|field_str| format!(" AND {} = ?", field_to_sql_quote(field_str)))
+            .unwrap_or_default()
+    );
+
+    let update_field_vec = field_vec
+        .iter()
+        .filter(|field| Some(get_table_field_name(field)) != version_field_name)
+        .copied()
+        .collect::<Vec<_>>();
+    let mut update_bind_quote_vec = fields_to_bind_quote(&self_ident, &update_field_vec);
+    update_bind_quote_vec.push(quote!(.bind(self.#id.clone())));
+    if let Some(version) = version {
+        update_bind_quote_vec.push(field_to_bind_quote(&self_ident, version));
+    }
+    let version_increment_quote = match version.and_then(|version| version.ident.as_ref()) {
+        Some(version_ident) => quote!(self.#version_ident += 1;),
+        None => quote!(),
+    };
+    //没有修改到数据时，区分数据不存在和版本号不一致
+    let count_by_id_sql = format!(
+        "{} WHERE {} = ?",
+        count_base_sql,
         field_to_sql_quote(&id.to_string())
     );
+    let get_not_updated_error_quote = |executor: TokenStream2| match version {
+        Some(_) => quote!(
+            match sqlx::query_scalar::<_, i64>(#count_by_id_sql)
+                .bind(self.#id.clone())
+                .fetch_one(#executor)
+                .await?
+            {
+                0 => #error::NotFound,
+                _ => #error::StaleVersion,
+            }
+        ),
+        None => quote!(#error::NotFound),
+    };
+    let update_not_updated_error = get_not_updated_error_quote(pool.clone());
+    let tran_update_not_updated_error = get_not_updated_error_quote(quote!(&mut **tran));
+
+    //批量修改，每个字段通过`CASE id WHEN ? THEN ?`一次修改一批数据
+    let row_ident = format_ident!("row");
+    let id_str = id.to_string();
+    let update_many_column_vec = update_field_vec
+        .iter()
+        .map(|field| get_table_field_name(field))
+        .collect::<Vec<_>>();
+    let update_many_column_count = update_many_column_vec.len();
+    let update_many_bind_vec = update_field_vec
+        .iter()
+        .map(|field| field_to_bind_quote(&row_ident, field))
+        .collect::<Vec<_>>();
+    let update_many_version = match &version_field_name {
+        Some(version_name) => quote!(Some(#version_name)),
+        None => quote!(None),
+    };
+    let (update_many_version_get, update_many_stale_arm) = match version {
+        Some(Field {
+            ident: Some(version_ident),
+            ty: version_ty,
+            ..
+        }) => (
+            quote!(sqlx::Row::try_get::<#version_ty, _>(lock_row, 1)?),
+            quote!(Some(version) if *version != row.#version_ident => sqlx_sqlhelper::UpdateOutcome::StaleVersion,),
+        ),
+        _ => (quote!(()), quote!()),
+    };
+    //开启历史表时，在修改之前把本批要修改的数据写入历史表
+    let update_many_history_quote = if options.history {
+        let write_history_in_sql = format!(
+            "INSERT INTO `{}_history` (`history_op`, `history_time`, `history_actor`, {}) SELECT ?, ?, ?, {} FROM {} WHERE {} IN ({{}})",
+            table_name_str,
+            select_field_sql,
+            select_field_sql,
+            table_name,
+            field_to_sql_quote(&id_str)
+        );
+        quote!(
+            let history_sql = format!(#write_history_in_sql, vec!["?"; update_vec.len()].join(", "));
+            let mut history_query = sqlx::query(&history_sql)
+                .bind(sqlx_sqlhelper::HistoryOp::Update.as_str())
+                .bind(chrono::Local::now().naive_local())
+                .bind(sqlx_sqlhelper::current_actor::<String>());
+            for &i in &update_vec {
+                history_query = history_query.bind(rows[i].#id.clone());
+            }
+            history_query.execute(&mut **tran).await?;
+        )
+    } else {
+        quote!()
+    };
+    let update_many_version_increment_quote =
+        match version.and_then(|version| version.ident.as_ref()) {
+            Some(version_ident) => quote!(row.#version_ident += 1;),
+            None => quote!(),
+        };
+    //只有主键时没有需要修改的字段，不生成批量修改函数
+    let update_many_fn = if update_many_column_vec.is_empty() {
+        quote!()
+    } else {
+        quote!(
+            /// 在一个事务中分批修改多条数据，返回每条数据的执行结果
+            ///
+            /// 校验失败、数据不存在、`#[version]`版本号不一致的数据不会修改，也不会影响其他数据。
+            /// 主键重复的数据只修改第一条，其他的返回`UpdateOutcome::Duplicate`。
+            /// 开启历史表时在同一个事务中写入修改前的数据。
+            /// 每条数据都会调用`SqlHelperHooks`中的`before_update`，修改成功的数据调用`after_update`，
+            /// 主键重复的数据不会调用钩子。
+            pub async fn update_many(rows: &mut [Self]) -> Result<Vec<sqlx_sqlhelper::UpdateOutcome>, #error> {
+                let mut tran = db::POOL.begin().await?;
+                let outcomes = Self::tran_update_many(&mut tran, rows).await?;
+                tran.commit().await?;
+                Ok(outcomes)
+            }
 
-    let update_bind_quote_vec = fields_to_bind_quote(&self_ident, &field_vec);
+            /// 和`update_many`相同，在事务中执行
+            pub async fn tran_update_many(tran: &mut sqlx::Transaction<'_, sqlx::MySql>, rows: &mut [Self]) -> Result<Vec<sqlx_sqlhelper::UpdateOutcome>, #error> {
+                let mut id_set = std::collections::HashSet::with_capacity(rows.len());
+                let mut outcomes = Vec::with_capacity(rows.len());
+                for row in rows.iter_mut() {
+                    if !id_set.insert(row.#id.clone()) {
+                        outcomes.push(sqlx_sqlhelper::UpdateOutcome::Duplicate);
+                        continue;
+                    }
+                    #hooks::before_update(row).await?;
+                    outcomes.push(match row.validate() {
+                        Ok(()) => sqlx_sqlhelper::UpdateOutcome::Updated,
+                        Err(e) => sqlx_sqlhelper::UpdateOutcome::Invalid(e),
+                    });
+                }
+                let valid_vec = (0..rows.len())
+                    .filter(|&i| outcomes[i].is_updated())
+                    .collect::<Vec<_>>();
+                for chunk in valid_vec.chunks(sqlx_sqlhelper::update_many_chunk_size(#update_many_column_count)) {
+                    //锁定本批数据，区分数据不存在和版本号不一致
+                    let lock_sql = sqlx_sqlhelper::update_many_lock_sql(#table_name_str, #id_str, #update_many_version, chunk.len());
+                    let mut lock_query = sqlx::query(&lock_sql);
+                    for &i in chunk {
+                        lock_query = lock_query.bind(rows[i].#id.clone());
+                    }
+                    let existing = lock_query
+                        .fetch_all(&mut **tran)
+                        .await?
+                        .iter()
+                        .map(|lock_row| Ok((sqlx::Row::try_get::<#id_ty, _>(lock_row, 0)?, #update_many_version_get)))
+                        .collect::<Result<std::collections::HashMap<_, _>, sqlx::Error>>()?;
+                    let mut update_vec = Vec::with_capacity(chunk.len());
+                    for &i in chunk {
+                        let row = &rows[i];
+                        outcomes[i] = match existing.get(&row.#id) {
+                            None => sqlx_sqlhelper::UpdateOutcome::NotFound,
+                            #update_many_stale_arm
+                            Some(_) => {
+                                update_vec.push(i);
+                                sqlx_sqlhelper::UpdateOutcome::Updated
+                            }
+                        };
+                    }
+                    if update_vec.is_empty() {
+                        continue;
+                    }
+                    #update_many_history_quote
+                    let sql = sqlx_sqlhelper::update_many_sql(
+                        #table_name_str,
+                        #id_str,
+                        &[#(#update_many_column_vec),*],
+                        #update_many_version,
+                        update_vec.len(),
+                    );
+                    let mut query = sqlx::query(&sql);
+                    #(
+                        for &i in &update_vec {
+                            let row = &rows[i];
+                            query = query.bind(row.#id.clone()) #update_many_bind_vec;
+                        }
+                    )*
+                    for &i in &update_vec {
+                        query = query.bind(rows[i].#id.clone());
+                    }
+                    query.execute(&mut **tran).await?;
+                }
+                for (row, outcome) in rows.iter_mut().zip(&outcomes) {
+                    if outcome.is_updated() {
+                        #update_many_version_increment_quote
+                        #hooks::after_update(row).await?;
+                    }
+                }
+                Ok(outcomes)
+            }
+        )
+    };
 
-    let update_auto_time_quote = get_auto_time_quote(&self_ident, None, &update_time);
+    let update_auto_time_quote = get_auto_time_quote(&self_ident, None, update_time_field);
+    let update_audit_quote = get_audit_quote(&self_ident, None, updated_by);
+    let update_history_op = quote!(sqlx_sqlhelper::HistoryOp::Update);
+    let update_body = get_execute_quote(
+        &options,
+        &pool,
+        quote!(self.#id),
+        &update_history_op,
+        quote!(#query(sql) #(#update_bind_quote_vec)*),
+    );
     let update_fn = quote!(
-        pub async fn update(&self) -> Result<bool, sqlx::Error> {
+        /// 数据不存在时返回`Error::NotFound`，`#[version]`版本号不一致时返回`Error::StaleVersion`
+        pub async fn update(&mut self) -> Result<(), #error> {
+            #hooks::before_update(self).await?;
+            self.validate()?;
             let sql = #update_sql;
-            #query(sql)
-            #(#update_bind_quote_vec)*
-            .bind(self.#id)
-            .execute(#pool).await.map(|f|f.rows_affected() > 0)
+            let rows_affected = { #update_body };
+            if rows_affected == 0 {
+                return Err(#update_not_updated_error);
+            }
+            #version_increment_quote
+            #hooks::after_update(self).await?;
+            Ok(())
         }
 
         /// 如果定义的update_time字段是`Default::default()`默认值，则更新为当前时间
         ///
         /// `Default::default()`一般为`1970-01-01T00:00:00`等
-        pub async fn update_auto_time(&mut self) -> Result<bool, sqlx::Error> {
+        ///
+        /// 如果定义了`updated_by`字段，则从审计上下文中获取当前操作人
+        pub async fn update_auto_time(&mut self) -> Result<(), #error> {
             #update_auto_time_quote
+            #update_audit_quote
             self.update().await
         }
     );
 
-    //保存或者修改函数
+    //新增或修改函数，通过`INSERT ... ON DUPLICATE KEY UPDATE`执行，修改时不会覆盖创建时间和创建人
+    let create_time_name = ast
+        .fields
+        .iter()
+        .find(|field| field.ident.is_some() && field.ident == create_time)
+        .map(get_table_field_name);
+    let created_by_name = created_by.map(get_table_field_name);
+    let integer_id = is_integer_type(extract_type_from_option(&id_ty).unwrap_or(&id_ty));
+    let upsert_sql = get_upsert_sql(
+        &table_name,
+        &id_str,
+        &table_field_name_vec,
+        integer_id,
+        &[create_time_name.as_deref(), created_by_name.as_deref()],
+        version_field_name.as_deref(),
+    );
+    let upsert_bind_quote_vec = fields_to_bind_quote(&self_ident, &field_vec);
+    //自增主键新增数据或者通过`LAST_INSERT_ID(id)`修改数据时，把主键赋值给`self`
+    let upsert_id_quote = match (integer_id, extract_type_from_option(&id_ty)) {
+        (false, _) => quote!(),
+        (true, Some(id_inner_ty)) => quote!(
+            if last_id > 0 {
+                self.#id = Some(last_id as #id_inner_ty);
+            }
+        ),
+        (true, None) => quote!(
+            if last_id > 0 {
+                self.#id = last_id as #id_ty;
+            }
+        ),
+    };
+    let get_upsert_execute_quote = |executor: TokenStream2| {
+        quote!(
+            let sql = #upsert_sql;
+            let last_id = #query(sql)
+            .bind(&self.#id)
+            #(#upsert_bind_quote_vec)*
+            .execute(#executor)
+            .await?
+            .last_insert_id();
+            #upsert_id_quote
+        )
+    };
+    //自定义钩子或者开启历史表时，先在事务中锁定并判断数据是否存在，再调用对应的钩子、写入历史表
+    //
+    //`ON DUPLICATE KEY UPDATE`在主键或任意唯一索引冲突时修改数据，所以按照主键和所有唯一索引查找冲突的数据
+    let upsert_fn = if options.hooks || options.history {
+        let unique_index_vec = indexes
+            .iter()
+            .filter(|index| index.unique)
+            .map(|index| index.columns.clone())
+            .collect::<Vec<_>>();
+        let upsert_lock_sql = get_upsert_lock_sql(&table_name, &id_str, &unique_index_vec);
+        let upsert_lock_bind_vec = unique_index_vec
+            .iter()
+            .flatten()
+            .filter_map(|column| {
+                column_field_vec
+                    .iter()
+                    .find(|field| get_table_field_name(field) == *column)
+            })
+            .map(|field| field_to_bind_quote(&self_ident, field))
+            .collect::<Vec<_>>();
+        let upsert_history_quote = if options.history {
+            quote!(
+                if existing {
+                    Self::write_history(tran, self.#id.clone(), #update_history_op).await?;
+                }
+            )
+        } else {
+            quote!()
+        };
+        let tran_upsert_execute_quote = get_upsert_execute_quote(quote!(&mut **tran));
+        quote!(
+            /// 通过`INSERT ... ON DUPLICATE KEY UPDATE`新增或修改数据，修改时不会覆盖创建时间和创建人
+            ///
+            /// 在事务中先按照主键和所有唯一索引锁定冲突的数据，存在时把该数据的主键赋值给`self`，
+            /// 调用`before_update`、`after_update`钩子并写入历史表，否则调用`before_insert`、`after_insert`钩子。
+            /// 主键和唯一索引分别与不同的数据冲突时返回`Error::MultipleRows`。
+            /// `#[version]`字段由数据库自增，不检查版本号
+            pub async fn upsert(&mut self) -> Result<(), #error> {
+                let mut tran = db::POOL.begin().await?;
+                self.tran_upsert(&mut tran).await?;
+                tran.commit().await?;
+                Ok(())
+            }
+
+            /// 和`upsert`相同，在事务中执行
+            pub async fn tran_upsert(&mut self, tran: &mut sqlx::Transaction<'_, sqlx::MySql>) -> Result<(), #error> {
+                let mut existing_ids = sqlx::query_scalar::<_, #id_ty>(#upsert_lock_sql)
+                    .bind(&self.#id)
+                    #(#upsert_lock_bind_vec)*
+                    .fetch_all(&mut **tran)
+                    .await?;
+                if existing_ids.len() > 1 {
+                    return Err(#error::MultipleRows);
+                }
+                let existing = match existing_ids.pop() {
+                    Some(existing_id) => {
+                        self.#id = existing_id;
+                        true
+                    }
+                    None => false,
+                };
+                match existing {
+                    true => #hooks::before_update(self).await?,
+                    false => #hooks::before_insert(self).await?,
+                }
+                self.validate()?;
+                #upsert_history_quote
+                #tran_upsert_execute_quote
+                match existing {
+                    true => #hooks::after_update(self).await,
+                    false => #hooks::after_insert(self).await,
+                }
+            }
+        )
+    } else {
+        let upsert_execute_quote = get_upsert_execute_quote(pool.clone());
+        let tran_upsert_execute_quote = get_upsert_execute_quote(quote!(&mut **tran));
+        quote!(
+            /// 通过`INSERT ... ON DUPLICATE KEY UPDATE`新增或修改数据，修改时不会覆盖创建时间和创建人
+            ///
+            /// 唯一索引冲突时修改冲突的数据，整数主键会把该数据的主键赋值给`self`。
+            /// `#[version]`字段由数据库自增，不检查版本号
+            pub async fn upsert(&mut self) -> Result<(), #error> {
+                self.validate()?;
+                #upsert_execute_quote
+                Ok(())
+            }
+
+            /// 和`upsert`相同，在事务中执行
+            pub async fn tran_upsert(&mut self, tran: &mut sqlx::Transaction<'_, sqlx::MySql>) -> Result<(), #error> {
+                self.validate()?;
+                #tran_upsert_execute_quote
+                Ok(())
+            }
+        )
+    };
+
+    //保存或者修改函数，根据`#[sql_helper(new_record = "...")]`判断是新增还是修改
+    let get_is_existing_quote = |executor: TokenStream2| match new_record {
+        NewRecord::Sentinel => quote!(self.#id > 0),
+        NewRecord::Option => quote!(self.#id.is_some()),
+        NewRecord::Exists | NewRecord::Upsert => quote!(
+            sqlx::query_scalar::<_, i64>(#count_by_id_sql)
+                .bind(&self.#id)
+                .fetch_one(#executor)
+                .await?
+                > 0
+        ),
+    };
+    let is_existing_quote = get_is_existing_quote(pool.clone());
+    let tran_is_existing_quote = get_is_existing_quote(quote!(&mut **tran));
+    let (save_or_update_body, save_or_update_auto_time_body) = match new_record {
+        NewRecord::Upsert => (
+            quote!(self.upsert().await),
+            quote!(
+                #insert_auto_time_quote
+                #insert_audit_quote
+                self.upsert().await
+            ),
+        ),
+        _ => (
+            quote!(
+                match #is_existing_quote {
+                    true => self.update().await,
+                    false => self.insert_returning_id().await.map(|_| ()),
+                }
+            ),
+            quote!(
+                match #is_existing_quote {
+                    true => self.update_auto_time().await,
+                    false => self.insert_auto_time_returning_id().await.map(|_| ()),
+                }
+            ),
+        ),
+    };
+    let (tran_save_or_update_body, tran_save_or_update_auto_time_body) = match new_record {
+        NewRecord::Upsert => (
+            quote!(self.tran_upsert(tran).await),
+            quote!(
+                #insert_auto_time_quote
+                #insert_audit_quote
+                self.tran_upsert(tran).await
+            ),
+        ),
+        _ => (
+            quote!(
+                match #tran_is_existing_quote {
+                    true => self.tran_update(tran).await,
+                    false => self.tran_insert(tran).await.map(|_| ()),
+                }
+            ),
+            quote!(
+                match #tran_is_existing_quote {
+                    true => self.tran_update_auto_time(tran).await,
+                    false => self.tran_insert_auto_time(tran).await.map(|_| ()),
+                }
+            ),
+        ),
+    };
     let save_or_update_fn = quote!(
-        /// 调用`save_or_update`方法时有一定风险
+        /// 根据`#[sql_helper(new_record = "...")]`判断是新增还是修改
         ///
-        /// `save_or_update`只是简单判断id是否大于0，大于0则更新，小于等于0则插入。
+        /// 默认整数主键大于0时修改，否则新增；`option`为`Some`时修改；`exists`根据数据库中是否存在该主键判断；
+        /// `upsert`通过`INSERT ... ON DUPLICATE KEY UPDATE`执行。
         ///
-        /// 此时如果手动将`id`赋值为大于0时，会出现更新其他数据的情况，请注意这一块。
-        pub async fn save_or_update(&mut self) -> Result<bool, sqlx::Error> {
-            match self.#id > 0 {
-                true => self.update().await,
-                //false => Self::add(self).await.map(|_| true),
-                false => self.insert().await.map(|_| true),
-            }
+        /// 默认方式下如果手动将`id`赋值为大于0时，会出现更新其他数据的情况，请注意这一块。
+        pub async fn save_or_update(&mut self) -> Result<(), #error> {
+            #save_or_update_body
         }
 
         /// 如果定义的update_time字段是`Default::default()`默认值，则更新为当前时间
         ///
         /// Default::default()一般为`1970-01-01T00:00:00`等
         ///
-        /// 调用`save_or_update`方法时有一定风险
-        ///
-        /// `save_or_update`只是简单判断id是否大于0，大于0则更新，小于等于0则插入。
-        ///
-        /// 此时如果手动将`id`赋值为大于0时，会出现更新其他数据的情况，请注意这一块。
-        pub async fn save_or_update_auto_time(&mut self) -> Result<bool, sqlx::Error> {
-            match self.#id > 0 {
-                true => self.update_auto_time().await,
-                //false => Self::add(self).await.map(|_| true),
-                false => self.insert_auto_time().await.map(|_| true),
-            }
+        /// 新增还是修改的判断方式与`save_or_update`相同
+        pub async fn save_or_update_auto_time(&mut self) -> Result<(), #error> {
+            #save_or_update_auto_time_body
         }
+
+        #upsert_fn
     );
 
     let mut new_auto_field_vec = vec![];
@@ -252,102 +778,168 @@ pub fn impl_sql_helper(ast: &ItemStruct) -> TokenStream {
         })
         .collect::<Vec<_>>();
 
+    //没有定义create_time、update_time字段时，new方法不包含对应的参数
+    let time_field_vec = [create_time_field, update_time_field]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    let time_ident_vec = time_field_vec
+        .iter()
+        .filter_map(|field| field.ident.as_ref())
+        .collect::<Vec<_>>();
+    let time_ty_vec = time_field_vec
+        .iter()
+        .map(|field| &field.ty)
+        .collect::<Vec<_>>();
+    let now_vec = time_field_vec
+        .iter()
+        .map(|field| get_now_quote(field))
+        .collect::<Vec<_>>();
     let new_fn = quote!(
-        pub fn new(#(#new_field_vec),*,#create_time: chrono::NaiveDateTime, #update_time: chrono::NaiveDateTime) -> Self {
+        #[allow(clippy::too_many_arguments)]
+        pub fn new(#(#new_field_vec,)* #(#time_ident_vec: #time_ty_vec),*) -> Self {
             Self{
-                #id: 0,
-                #(#new_self_field_vec),*,
-                #create_time,
-                #update_time
+                #id: Default::default(),
+                #(#new_self_field_vec,)*
+                #(#time_ident_vec),*
             }
         }
+        #[allow(clippy::too_many_arguments)]
         pub fn new_common(#(#new_field_vec),*) -> Self {
             Self::new(
-                #(#new_self_field_vec),*,
-                chrono::Local::now().naive_local(),
-                chrono::Local::now().naive_local()
+                #(#new_self_field_vec,)*
+                #(#now_vec),*
             )
         }
     );
 
     let base_page_select_sql = format!("{} WHERE {{}} LIMIT {{}}, {{}}", select_base_sql);
 
-    let base_page_fn = quote!(
-        pub async fn base_page(
-            page_index: i32,
-            page_size: i32,
-            where_sql: &str,
-            args: sqlx::mysql::MySqlArguments,
-        ) -> Result<(Vec<Self>, i32, i32, i32), sqlx::Error> {
-            let mut index = page_index - 1;
-            if index < 0 {
-                index = 0;
+    //总数函数
+    let base_count_sql = format!("{} WHERE {{}}", count_base_sql);
+    //聚合函数，条件与`list_by`相同，`prefix`为`tran_`时生成事务中执行的版本
+    let tran = quote!(tran: &mut sqlx::Transaction<'_, sqlx::MySql>);
+    let get_aggregate_fn_quote = |prefix: &str, param: TokenStream2, executor: TokenStream2| {
+        let count_fn = format_ident!("{}count", prefix);
+        let exists_fn = format_ident!("{}exists", prefix);
+        let sum_fn = format_ident!("{}sum", prefix);
+        let min_fn = format_ident!("{}min", prefix);
+        let max_fn = format_ident!("{}max", prefix);
+        let avg_fn = format_ident!("{}avg", prefix);
+        let group_count_fn = format_ident!("{}group_count", prefix);
+        let group_count_by_fn = format_ident!("{}group_count_by", prefix);
+        quote!(
+            /// 查询满足条件的数量
+            pub async fn #count_fn(#param where_sql: &str, args: sqlx::mysql::MySqlArguments) -> Result<i64, #error> {
+                sqlx_sqlhelper::count::<Self, _>(#executor, where_sql, args).await
             }
-            let rows = page_size;
 
+            /// 是否存在满足条件的数据
+            pub async fn #exists_fn(#param where_sql: &str, args: sqlx::mysql::MySqlArguments) -> Result<bool, #error> {
+                sqlx_sqlhelper::exists::<Self, _>(#executor, where_sql, args).await
+            }
 
-            let (count,) = Self::base_count(where_sql, args.clone()).await?;
+            /// 字段求和，整数和`DECIMAL`字段使用`Decimal`（开启`rust_decimal` feature），浮点数字段使用`f64`
+            pub async fn #sum_fn<V>(#param column: &str, where_sql: &str, args: sqlx::mysql::MySqlArguments) -> Result<Option<V>, #error>
+            where
+                V: for<'r> sqlx::Decode<'r, sqlx::MySql> + sqlx::Type<sqlx::MySql> + Send + Unpin,
+            {
+                sqlx_sqlhelper::aggregate::<Self, V, _>(#executor, sqlx_sqlhelper::Aggregate::Sum, column, where_sql, args).await
+            }
 
-            let arr = match count > 0 {
-                true => {
-                    let sql = format!(
-                        #base_page_select_sql,
-                        where_sql,
-                        index * rows,
-                        rows
-                    );
-                    sqlx::query_as_with::<_, Self, sqlx::mysql::MySqlArguments>(&sql, args)
-                        .fetch_all(#pool)
-                        .await?
-                }
-                false => Vec::new(),
-            };
+            /// 字段最小值，返回字段本身的类型
+            pub async fn #min_fn<V>(#param column: &str, where_sql: &str, args: sqlx::mysql::MySqlArguments) -> Result<Option<V>, #error>
+            where
+                V: for<'r> sqlx::Decode<'r, sqlx::MySql> + sqlx::Type<sqlx::MySql> + Send + Unpin,
+            {
+                sqlx_sqlhelper::aggregate::<Self, V, _>(#executor, sqlx_sqlhelper::Aggregate::Min, column, where_sql, args).await
+            }
 
-            //总共有几页
-            let total_page = (count as f32 / page_size as f32).ceil();
+            /// 字段最大值，返回字段本身的类型
+            pub async fn #max_fn<V>(#param column: &str, where_sql: &str, args: sqlx::mysql::MySqlArguments) -> Result<Option<V>, #error>
+            where
+                V: for<'r> sqlx::Decode<'r, sqlx::MySql> + sqlx::Type<sqlx::MySql> + Send + Unpin,
+            {
+                sqlx_sqlhelper::aggregate::<Self, V, _>(#executor, sqlx_sqlhelper::Aggregate::Max, column, where_sql, args).await
+            }
 
-            Ok((arr, count, index + 1, total_page as i32))
-        }
-    );
+            /// 字段平均值，整数和`DECIMAL`字段使用`Decimal`（开启`rust_decimal` feature），浮点数字段使用`f64`
+            pub async fn #avg_fn<V>(#param column: &str, where_sql: &str, args: sqlx::mysql::MySqlArguments) -> Result<Option<V>, #error>
+            where
+                V: for<'r> sqlx::Decode<'r, sqlx::MySql> + sqlx::Type<sqlx::MySql> + Send + Unpin,
+            {
+                sqlx_sqlhelper::aggregate::<Self, V, _>(#executor, sqlx_sqlhelper::Aggregate::Avg, column, where_sql, args).await
+            }
 
-    //总数函数
-    let base_count_sql = format!("{} WHERE {{}}", count_base_sql);
-    let base_count_fn = quote!(
-        pub async fn base_count(
-            where_sql: &str,
-            args: sqlx::mysql::MySqlArguments,
-        ) -> Result<(i32,), sqlx::Error> {
-            let count_sql = format!(#base_count_sql, where_sql);
-            sqlx::query_as_with::<_, (i32,), sqlx::mysql::MySqlArguments>(
-                &count_sql,
-                args,
-            )
-            .fetch_one(#pool)
-            .await
-        }
-    );
+            /// 按照字段分组统计数量
+            pub async fn #group_count_fn<V>(#param column: &str) -> Result<Vec<(V, i64)>, #error>
+            where
+                V: for<'r> sqlx::Decode<'r, sqlx::MySql> + sqlx::Type<sqlx::MySql> + Send + Unpin,
+            {
+                sqlx_sqlhelper::group_count::<Self, V, _>(#executor, column, "", Default::default()).await
+            }
+
+            /// 按照字段分组统计满足条件的数量
+            pub async fn #group_count_by_fn<V>(#param column: &str, where_sql: &str, args: sqlx::mysql::MySqlArguments) -> Result<Vec<(V, i64)>, #error>
+            where
+                V: for<'r> sqlx::Decode<'r, sqlx::MySql> + sqlx::Type<sqlx::MySql> + Send + Unpin,
+            {
+                sqlx_sqlhelper::group_count::<Self, V, _>(#executor, column, where_sql, args).await
+            }
+        )
+    };
+    let aggregate_fn = get_aggregate_fn_quote("", quote!(), pool.clone());
+    let tran_aggregate_fn = get_aggregate_fn_quote("tran_", quote!(#tran,), quote!(&mut **tran));
 
     let tran_insert_fn = quote!(
-        pub async fn tran_insert(&mut self, tran: &mut sqlx::Transaction<'_, sqlx::MySql>) -> Result<i32, sqlx::Error> {
+        pub async fn tran_insert(&mut self, tran: &mut sqlx::Transaction<'_, sqlx::MySql>) -> Result<#id_ty, #error> {
+            #hooks::before_insert(self).await?;
+            self.validate()?;
             let sql = #insert_sql;
-            let last_id = #query(sql)
-            #(#insert_bind_quote_vec)*
-            .execute(&mut **tran)
-            .await?
-            .last_insert_id();
-            self.#id = last_id as i32;
-            Ok(self.#id)
+            #tran_insert_execute_quote
+            #hooks::after_insert(self).await?;
+            Ok(self.#id.clone())
         }
     );
 
-    let tran_update_fn = quote!(
-        pub async fn tran_update(&self, tran: &mut sqlx::Transaction<'_, sqlx::MySql>) -> Result<bool, sqlx::Error> {
-            let sql = #update_sql;
-            #query(sql)
+    //开启历史表时在保存点中先写入历史记录，没有数据被修改（数据不存在或版本号不一致）时回滚保存点，
+    //避免调用方提交事务后留下没有对应修改的历史记录
+    let tran_update_execute_quote = if options.history {
+        quote!(
+            let mut savepoint = sqlx::Connection::begin(&mut **tran).await?;
+            Self::write_history(&mut savepoint, self.#id.clone(), #update_history_op).await?;
+            let rows_affected = #query(sql)
+            #(#update_bind_quote_vec)*
+            .execute(&mut *savepoint)
+            .await?
+            .rows_affected();
+            match rows_affected > 0 {
+                true => savepoint.commit().await?,
+                false => savepoint.rollback().await?,
+            }
+        )
+    } else {
+        quote!(
+            let rows_affected = #query(sql)
             #(#update_bind_quote_vec)*
-            .bind(self.#id)
             .execute(&mut **tran)
-            .await.map(|f|f.rows_affected() > 0)
+            .await?
+            .rows_affected();
+        )
+    };
+    let tran_update_fn = quote!(
+        /// 数据不存在时返回`Error::NotFound`，`#[version]`版本号不一致时返回`Error::StaleVersion`
+        pub async fn tran_update(&mut self, tran: &mut sqlx::Transaction<'_, sqlx::MySql>) -> Result<(), #error> {
+            #hooks::before_update(self).await?;
+            self.validate()?;
+            let sql = #update_sql;
+            #tran_update_execute_quote
+            if rows_affected == 0 {
+                return Err(#tran_update_not_updated_error);
+            }
+            #version_increment_quote
+            #hooks::after_update(self).await?;
+            Ok(())
         }
     );
 
@@ -356,29 +948,90 @@ pub fn impl_sql_helper(ast: &ItemStruct) -> TokenStream {
         select_base_sql,
         field_to_sql_quote(&id.to_string()),
     );
-    let get_by_id_in_fn = quote! {
-        pub async fn get_by_id_in(ids: Vec<i32>) -> Result<Vec<Self>, sqlx::Error> {
-            let sql = format!(
-                #get_by_id_in_sql,
-                ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(", ")
-            );
-            #query_as(&sql)
-            .fetch_all(#pool)
-            .await
-        }
-    };
-
     let list_by_sql = format!("{} WHERE 1=1 {{}}", select_base_sql);
 
-    let list_by_fn = quote! {
-        pub async fn list_by(where_sql: &str, args: sqlx::mysql::MySqlArguments) -> Result<Vec<Self>, sqlx::Error> {
-            let sql = format!(#list_by_sql, where_sql);
-            sqlx::query_as_with::<_, Self, sqlx::mysql::MySqlArguments>(&sql, args)
+    let projection_fn = quote! {
+        /// 只查询投影结构体中的字段，条件与`list_by`相同
+        pub async fn list_as<P: sqlx_sqlhelper::Projection<Of = Self>>(
+            where_sql: &str,
+            args: sqlx::mysql::MySqlArguments,
+        ) -> Result<Vec<P>, #error> {
+            let sql = format!("{} WHERE 1=1 {}", P::select_base_sql(), where_sql);
+            Ok(sqlx::query_as_with::<_, P, sqlx::mysql::MySqlArguments>(&sql, args)
+                        .fetch_all(#pool)
+                        .await?)
+        }
+
+        /// 只查询投影结构体中的字段，条件与`base_page`相同
+        pub async fn page_as<P: sqlx_sqlhelper::Projection<Of = Self>>(
+            page_index: i32,
+            page_size: i32,
+            where_sql: &str,
+            args: sqlx::mysql::MySqlArguments,
+        ) -> Result<(Vec<P>, i32, i32, i32), #error> {
+            let index = (page_index - 1).max(0);
+            let (count,) = Self::base_count(where_sql, args.clone()).await?;
+            let arr = match count > 0 {
+                true => {
+                    let sql = format!(
+                        "{} WHERE {} LIMIT {}, {}",
+                        P::select_base_sql(),
+                        where_sql,
+                        index * page_size,
+                        page_size
+                    );
+                    sqlx::query_as_with::<_, P, sqlx::mysql::MySqlArguments>(&sql, args)
                         .fetch_all(#pool)
-                        .await
+                        .await?
+                }
+                false => Vec::new(),
+            };
+            let total_page = (count as f32 / page_size as f32).ceil();
+            Ok((arr, count, index + 1, total_page as i32))
         }
     };
 
+    let first_by_sql = format!("{} WHERE 1=1 {{}} LIMIT 1", select_base_sql);
+    let one_by_sql = format!("{} WHERE 1=1 {{}} LIMIT 2", select_base_sql);
+    //唯一字段的查找函数
+    let unique_fn_vec = field_vec
+        .iter()
+        .filter(|field| field_attr_exists(field, UNIQUE_NAME))
+        .filter_map(|field| {
+            let field_ident = field.ident.as_ref()?;
+            let column = field_to_sql_quote(&get_table_field_name(field));
+            let get_by_fn_name = format_ident!("get_by_{}", field_ident);
+            let exists_by_fn_name = format_ident!("exists_by_{}", field_ident);
+            let get_by_sql = format!("{} WHERE {} = ?", select_base_sql, column);
+            let exists_by_sql = format!(
+                "SELECT EXISTS(SELECT 1 FROM {} WHERE {} = ?)",
+                table_name, column
+            );
+            let ty = extract_type_from_option(&field.ty).unwrap_or(&field.ty);
+            let param_ty = match ty {
+                syn::Type::Path(type_path) if type_path.path.is_ident("String") => quote!(&str),
+                _ => quote!(#ty),
+            };
+            Some(quote!(
+                /// 数据不存在时返回`Error::NotFound`
+                pub async fn #get_by_fn_name(#field_ident: #param_ty) -> Result<Self, #error> {
+                    Ok(#query_as(#get_by_sql)
+                    .bind(#field_ident)
+                    .fetch_one(#pool)
+                    .await?)
+                }
+
+                pub async fn #exists_by_fn_name(#field_ident: #param_ty) -> Result<bool, #error> {
+                    let (exists,) = sqlx::query_as::<_, (i64,)>(#exists_by_sql)
+                    .bind(#field_ident)
+                    .fetch_one(#pool)
+                    .await?;
+                    Ok(exists > 0)
+                }
+            ))
+        })
+        .collect::<Vec<_>>();
+
     let select_sql = format!("{} WHERE 1=1 {{}}", select_base_sql);
 
     let select_sql_fn = quote! {
@@ -387,11 +1040,453 @@ pub fn impl_sql_helper(ast: &ItemStruct) -> TokenStream {
         }
     };
 
+    //事务函数
+    //批量修改、删除，条件为空时需要`Filter::allow_full_table`
+    //
+    //根据条件修改、删除时无法得到修改前的数据，开启历史表时不生成
+    let bulk_fn = if options.history {
+        quote!()
+    } else {
+        quote!(
+            /// 批量修改满足条件的数据，返回受影响的行数
+            ///
+            /// 不会调用`SqlHelperHooks`中的钩子
+            pub async fn update_where(filter: sqlx_sqlhelper::Filter, set: sqlx_sqlhelper::SetClause) -> Result<u64, #error> {
+                sqlx_sqlhelper::update_where::<Self, _>(#pool, filter, set).await
+            }
+
+            /// 批量删除满足条件的数据，返回受影响的行数
+            ///
+            /// 不会调用`SqlHelperHooks`中的钩子
+            pub async fn delete_where(filter: sqlx_sqlhelper::Filter) -> Result<u64, #error> {
+                sqlx_sqlhelper::delete_where::<Self, _>(#pool, filter).await
+            }
+
+            pub async fn tran_update_where(#tran, filter: sqlx_sqlhelper::Filter, set: sqlx_sqlhelper::SetClause) -> Result<u64, #error> {
+                sqlx_sqlhelper::update_where::<Self, _>(&mut **tran, filter, set).await
+            }
+
+            pub async fn tran_delete_where(#tran, filter: sqlx_sqlhelper::Filter) -> Result<u64, #error> {
+                sqlx_sqlhelper::delete_where::<Self, _>(&mut **tran, filter).await
+            }
+        )
+    };
+
+    //主键、条件查询以及分页函数，`prefix`为`tran_`时生成事务中执行的版本，`arg`为调用同类函数时传入的参数
+    let get_query_fn_quote = |prefix: &str,
+                              param: TokenStream2,
+                              arg: TokenStream2,
+                              executor: TokenStream2| {
+        let get_by_id_fn = format_ident!("{}get_by_id", prefix);
+        let find_by_id_fn = format_ident!("{}find_by_id", prefix);
+        let list_fn = format_ident!("{}list", prefix);
+        let get_by_id_in_fn = format_ident!("{}get_by_id_in", prefix);
+        let list_by_fn = format_ident!("{}list_by", prefix);
+        let first_by_fn = format_ident!("{}first_by", prefix);
+        let one_by_fn = format_ident!("{}one_by", prefix);
+        let base_page_fn = format_ident!("{}base_page", prefix);
+        let base_count_fn = format_ident!("{}base_count", prefix);
+        quote!(
+            /// 数据不存在时返回`Error::NotFound`
+            pub async fn #get_by_id_fn(#param #id: #id_ty) -> Result<Self, #error> {
+                Ok(#query_as(#get_by_id_sql)
+                .bind(#id)
+                .fetch_one(#executor)
+                .await?)
+            }
+
+            /// 数据不存在时返回`None`
+            pub async fn #find_by_id_fn(#param #id: #id_ty) -> Result<Option<Self>, #error> {
+                Ok(#query_as(#get_by_id_sql)
+                .bind(#id)
+                .fetch_optional(#executor)
+                .await?)
+            }
+
+            pub async fn #list_fn(#param) -> Result<Vec<Self>, #error> {
+                Ok(#query_as(#select_base_sql)
+                .fetch_all(#executor)
+                .await?)
+            }
+
+            pub async fn #get_by_id_in_fn(#param ids: Vec<#id_ty>) -> Result<Vec<Self>, #error> {
+                //主键通过参数绑定，支持字符串等非整数主键
+                if ids.is_empty() {
+                    return Ok(Vec::new());
+                }
+                let sql = format!(#get_by_id_in_sql, vec!["?"; ids.len()].join(", "));
+                let mut query = #query_as(&sql);
+                for id in ids {
+                    query = query.bind(id);
+                }
+                Ok(query.fetch_all(#executor).await?)
+            }
+
+            pub async fn #list_by_fn(#param where_sql: &str, args: sqlx::mysql::MySqlArguments) -> Result<Vec<Self>, #error> {
+                let sql = format!(#list_by_sql, where_sql);
+                Ok(sqlx::query_as_with::<_, Self, sqlx::mysql::MySqlArguments>(&sql, args)
+                            .fetch_all(#executor)
+                            .await?)
+            }
+
+            /// 获取`list_by`条件匹配的第一条数据，没有匹配时返回`None`
+            pub async fn #first_by_fn(#param where_sql: &str, args: sqlx::mysql::MySqlArguments) -> Result<Option<Self>, #error> {
+                let sql = format!(#first_by_sql, where_sql);
+                Ok(sqlx::query_as_with::<_, Self, sqlx::mysql::MySqlArguments>(&sql, args)
+                            .fetch_optional(#executor)
+                            .await?)
+            }
+
+            /// 获取`list_by`条件匹配的唯一一条数据，没有匹配时返回`None`，匹配多条时返回`Error::MultipleRows`
+            pub async fn #one_by_fn(#param where_sql: &str, args: sqlx::mysql::MySqlArguments) -> Result<Option<Self>, #error> {
+                let sql = format!(#one_by_sql, where_sql);
+                let mut rows = sqlx::query_as_with::<_, Self, sqlx::mysql::MySqlArguments>(&sql, args)
+                            .fetch_all(#executor)
+                            .await?;
+                match rows.len() {
+                    0 | 1 => Ok(rows.pop()),
+                    _ => Err(#error::MultipleRows),
+                }
+            }
+
+            pub async fn #base_page_fn(
+                #param
+                page_index: i32,
+                page_size: i32,
+                where_sql: &str,
+                args: sqlx::mysql::MySqlArguments,
+            ) -> Result<(Vec<Self>, i32, i32, i32), #error> {
+                let mut index = page_index - 1;
+                if index < 0 {
+                    index = 0;
+                }
+                let rows = page_size;
+
+                let (count,) = Self::#base_count_fn(#arg where_sql, args.clone()).await?;
+
+                let arr = match count > 0 {
+                    true => {
+                        let sql = format!(
+                            #base_page_select_sql,
+                            where_sql,
+                            index * rows,
+                            rows
+                        );
+                        sqlx::query_as_with::<_, Self, sqlx::mysql::MySqlArguments>(&sql, args)
+                            .fetch_all(#executor)
+                            .await?
+                    }
+                    false => Vec::new(),
+                };
+
+                //总共有几页
+                let total_page = (count as f32 / page_size as f32).ceil();
+
+                Ok((arr, count, index + 1, total_page as i32))
+            }
+
+            pub async fn #base_count_fn(
+                #param
+                where_sql: &str,
+                args: sqlx::mysql::MySqlArguments,
+            ) -> Result<(i32,), #error> {
+                let count_sql = format!(#base_count_sql, where_sql);
+                Ok(sqlx::query_as_with::<_, (i32,), sqlx::mysql::MySqlArguments>(
+                    &count_sql,
+                    args,
+                )
+                .fetch_one(#executor)
+                .await?)
+            }
+        )
+    };
+    let query_fn = get_query_fn_quote("", quote!(), quote!(), pool.clone());
+    let tran_query_fn =
+        get_query_fn_quote("tran_", quote!(#tran,), quote!(tran,), quote!(&mut **tran));
+
+    //悲观锁函数，只能在事务中使用
+    let lock_fn_vec = [
+        ("for_update", "FOR UPDATE"),
+        ("lock_in_share_mode", "LOCK IN SHARE MODE"),
+    ]
+    .iter()
+    .map(|(fn_suffix, lock_sql)| {
+        let get_by_id_lock_fn = format_ident!("tran_get_by_id_{}", fn_suffix);
+        let get_by_id_lock_sql = format!("{} {}", get_by_id_sql, lock_sql);
+        let list_by_lock_fn = format_ident!("tran_list_by_{}", fn_suffix);
+        let list_by_lock_sql = format!("{} WHERE 1=1 {{}} {}", select_base_sql, lock_sql);
+        let lock_doc = format!(" 查询时加`{}`锁，锁在事务结束时释放", lock_sql);
+        quote!(
+            #[doc = #lock_doc]
+            pub async fn #get_by_id_lock_fn(#tran, #id: #id_ty) -> Result<Self, #error> {
+                Ok(#query_as(#get_by_id_lock_sql)
+                .bind(#id)
+                .fetch_one(&mut **tran)
+                .await?)
+            }
+
+            #[doc = #lock_doc]
+            pub async fn #list_by_lock_fn(#tran, where_sql: &str, args: sqlx::mysql::MySqlArguments) -> Result<Vec<Self>, #error> {
+                let sql = format!(#list_by_lock_sql, where_sql);
+                Ok(sqlx::query_as_with::<_, Self, sqlx::mysql::MySqlArguments>(&sql, args)
+                            .fetch_all(&mut **tran)
+                            .await?)
+            }
+        )
+    })
+    .collect::<Vec<_>>();
+
+    let tran_delete_history_quote = if options.history {
+        quote!(Self::write_history(tran, #id.clone(), #delete_history_op).await?;)
+    } else {
+        quote!()
+    };
+    let tran_delete_by_id_fn = if options.hooks {
+        quote!(
+            pub async fn tran_delete_by_id(#tran, #id: #id_ty) -> Result<bool, #error> {
+                match Self::tran_get_by_id(tran, #id).await {
+                    Ok(row) => row.tran_delete(tran).await,
+                    Err(#error::NotFound) => Ok(false),
+                    Err(e) => Err(e),
+                }
+            }
+        )
+    } else {
+        quote!(
+            pub async fn tran_delete_by_id(#tran, #id: #id_ty) -> Result<bool, #error> {
+                #tran_delete_history_quote
+                let rows_affected = #query(#delete_sql)
+                .bind(#id)
+                .execute(&mut **tran)
+                .await?
+                .rows_affected();
+                Ok(rows_affected > 0)
+            }
+        )
+    };
+    let tran_write_fn = quote!(
+        pub async fn tran_delete(&self, #tran) -> Result<bool, #error> {
+            #hooks::before_delete(self).await?;
+            let #id = self.#id.clone();
+            #tran_delete_history_quote
+            let rows_affected = #query(#delete_sql)
+            .bind(#id)
+            .execute(&mut **tran)
+            .await?
+            .rows_affected();
+            #hooks::after_delete(self).await?;
+            Ok(rows_affected > 0)
+        }
+
+        #tran_delete_by_id_fn
+
+        /// 和`insert_auto_time`相同，在事务中执行
+        pub async fn tran_insert_auto_time(&mut self, #tran) -> Result<#id_ty, #error> {
+            #insert_auto_time_quote
+            #insert_audit_quote
+            self.tran_insert(tran).await
+        }
+
+        /// 和`update_auto_time`相同，在事务中执行
+        pub async fn tran_update_auto_time(&mut self, #tran) -> Result<(), #error> {
+            #update_auto_time_quote
+            #update_audit_quote
+            self.tran_update(tran).await
+        }
+
+        /// 和`save_or_update`相同，在事务中执行
+        pub async fn tran_save_or_update(&mut self, #tran) -> Result<(), #error> {
+            #tran_save_or_update_body
+        }
+
+        /// 和`save_or_update_auto_time`相同，在事务中执行
+        pub async fn tran_save_or_update_auto_time(&mut self, #tran) -> Result<(), #error> {
+            #tran_save_or_update_auto_time_body
+        }
+    );
+
+    //历史表函数
+    let history_fn = if options.history {
+        let history_table_name = format!("`{}_history`", table_name_str);
+        let write_history_sql = format!(
+            "INSERT INTO {} (`history_op`, `history_time`, `history_actor`, {}) SELECT ?, ?, ?, {} FROM {} WHERE {} = ?",
+            history_table_name,
+            select_field_sql,
+            select_field_sql,
+            table_name,
+            field_to_sql_quote(&id.to_string())
+        );
+        let history_sql = format!(
+            "SELECT `history_id`, `history_op`, `history_time`, `history_actor`, {} FROM {} WHERE {} = ? ORDER BY `history_id`",
+            select_field_sql,
+            history_table_name,
+            field_to_sql_quote(&id.to_string())
+        );
+        quote!(
+            /// 在事务中把当前数据库中的数据写入历史表
+            async fn write_history(
+                tran: &mut sqlx::Transaction<'_, sqlx::MySql>,
+                #id: #id_ty,
+                op: sqlx_sqlhelper::HistoryOp,
+            ) -> Result<(), #error> {
+                #query(#write_history_sql)
+                .bind(op.as_str())
+                .bind(chrono::Local::now().naive_local())
+                .bind(sqlx_sqlhelper::current_actor::<String>())
+                .bind(#id)
+                .execute(&mut **tran)
+                .await?;
+                Ok(())
+            }
+
+            /// 获取历史表中的记录，按写入顺序排序
+            pub async fn history(#id: #id_ty) -> Result<Vec<sqlx_sqlhelper::History<Self>>, #error> {
+                Ok(sqlx::query_as::<_, sqlx_sqlhelper::History<Self>>(#history_sql)
+                .bind(#id)
+                .fetch_all(#pool)
+                .await?)
+            }
+        )
+    } else {
+        quote!()
+    };
+
+    //未自定义钩子时自动生成空实现
+    let hooks_impl = if options.hooks {
+        quote!()
+    } else {
+        quote!(
+            impl #hooks for #struct_name {}
+        )
+    };
+
+    //Crud trait实现，方法都委托给同名的inherent方法
+    let column_name_vec = std::iter::once(id.to_string())
+        .chain(table_field_name_vec.iter().cloned())
+        .collect::<Vec<_>>();
+    let table_meta = match get_table_meta_quote(
+        struct_name,
+        &table_name_str,
+        &ast.fields,
+        &id,
+        auto_increment,
+        &field_vec,
+        &indexes,
+    ) {
+        Ok(table_meta) => table_meta,
+        Err(e) => return e.into_compile_error().into(),
+    };
+    let meta_const = quote!(
+        /// 表的元数据
+        pub const META: sqlx_sqlhelper::TableMeta = #table_meta;
+
+        /// 根据表元数据生成`CREATE TABLE`语句
+        pub fn create_table_sql() -> String {
+            Self::META.create_table_sql()
+        }
+
+        /// 表不存在时创建表
+        pub async fn create_table_if_not_exists(
+            pool: &sqlx::Pool<sqlx::MySql>,
+        ) -> Result<(), #error> {
+            #query(&Self::META.create_table_if_not_exists_sql())
+                .execute(pool)
+                .await?;
+            Ok(())
+        }
+    );
+
+    //JOIN查询时从带有前缀的字段中读取数据，不是表字段的struct字段使用默认值
+    let prefixed_field_vec = ast
+        .fields
+        .iter()
+        .filter_map(|field| {
+            let field_ident = field.ident.as_ref()?;
+            let column = if field_ident == &id {
+                Some(id.to_string())
+            } else if field_vec.iter().any(|f| std::ptr::eq(*f, field)) {
+                Some(get_table_field_name(field))
+            } else {
+                None
+            };
+            Some(match column {
+                Some(column) => quote!(
+                    #field_ident: sqlx::Row::try_get(row, format!("{}{}", prefix, #column).as_str())?
+                ),
+                None => quote!(#field_ident: Default::default()),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let crud_impl = quote!(
+        impl sqlx_sqlhelper::Crud for #struct_name {
+            type Id = #id_ty;
+
+            const TABLE: &'static str = #table_name_str;
+
+            const COLUMNS: &'static [&'static str] = &[#(#column_name_vec),*];
+
+            const META: sqlx_sqlhelper::TableMeta = #struct_name::META;
+
+            fn id(&self) -> Self::Id {
+                self.#id.clone()
+            }
+
+            fn from_row_prefixed(row: &sqlx::mysql::MySqlRow, prefix: &str) -> Result<Self, sqlx::Error> {
+                Ok(Self {
+                    #(#prefixed_field_vec),*
+                })
+            }
+
+            async fn get_by_id(#id: Self::Id) -> Result<Self, #error> {
+                Self::get_by_id(#id).await
+            }
+
+            async fn find_by_id(#id: Self::Id) -> Result<Option<Self>, #error> {
+                Self::find_by_id(#id).await
+            }
+
+            async fn list() -> Result<Vec<Self>, #error> {
+                Self::list().await
+            }
+
+            async fn list_by(where_sql: &str, args: sqlx::mysql::MySqlArguments) -> Result<Vec<Self>, #error> {
+                Self::list_by(where_sql, args).await
+            }
+
+            async fn insert(&mut self) -> Result<Self, #error> {
+                Self::insert(self).await
+            }
+
+            async fn update(&mut self) -> Result<(), #error> {
+                Self::update(self).await
+            }
+
+            async fn save_or_update(&mut self) -> Result<(), #error> {
+                Self::save_or_update(self).await
+            }
+
+            async fn delete(&self) -> Result<bool, #error> {
+                Self::delete(self).await
+            }
+
+            async fn delete_by_id(#id: Self::Id) -> Result<bool, #error> {
+                Self::delete_by_id(#id).await
+            }
+        }
+    );
+
+    let has_relation_fn = get_has_relation_fn(&options, struct_name, &id, &pool);
+
     let gen = quote!(
+        #hooks_impl
+
+        #crud_impl
+
         impl #struct_name {
-            #get_by_id_fn
+            #meta_const
 
-            #list_fn
+            #query_fn
 
             #delete_fn
 
@@ -405,24 +1500,141 @@ pub fn impl_sql_helper(ast: &ItemStruct) -> TokenStream {
 
             #new_fn
 
-            #base_page_fn
-
-            #base_count_fn
-
             #tran_insert_fn
 
             #tran_update_fn
 
-            #get_by_id_in_fn
+            #tran_query_fn
+
+            #(#lock_fn_vec)*
+
+            #tran_write_fn
 
-            #list_by_fn
+            #projection_fn
+
+            #aggregate_fn
+
+            #tran_aggregate_fn
+
+            #bulk_fn
+
+            #update_many_fn
+
+            #(#unique_fn_vec)*
 
             #select_sql_fn
+
+            #history_fn
+
+            #validate_fn
+
+            #belongs_to_fn
+
+            #has_relation_fn
         }
     );
     gen.into()
 }
 
+/// 生成执行修改、删除语句的代码，返回受影响的行数
+///
+/// 开启历史表时会在同一个事务中先写入历史记录，没有数据受影响时回滚事务
+fn get_execute_quote(
+    options: &SqlHelperOptions,
+    pool: &TokenStream2,
+    id_quote: TokenStream2,
+    history_op: &TokenStream2,
+    query_quote: TokenStream2,
+) -> TokenStream2 {
+    if options.history {
+        quote!(
+            let mut tran = db::POOL.begin().await?;
+            Self::write_history(&mut tran, #id_quote.clone(), #history_op).await?;
+            let rows_affected = #query_quote
+            .execute(&mut *tran)
+            .await?
+            .rows_affected();
+            if rows_affected > 0 {
+                tran.commit().await?;
+            }
+            rows_affected
+        )
+    } else {
+        quote!(
+            #query_quote
+            .execute(#pool)
+            .await?
+            .rows_affected()
+        )
+    }
+}
+
+/// `INSERT ... ON DUPLICATE KEY UPDATE`语句，`keep_columns`为修改时不覆盖的字段
+///
+/// 整数主键通过`LAST_INSERT_ID(id)`返回冲突数据的主键，`#[version]`字段自增
+fn get_upsert_sql(
+    table_name: &str,
+    id: &str,
+    columns: &[String],
+    integer_id: bool,
+    keep_columns: &[Option<&str>],
+    version: Option<&str>,
+) -> String {
+    let id_column = field_to_sql_quote(id);
+    let assignment_vec = integer_id
+        .then(|| format!("{} = LAST_INSERT_ID({})", id_column, id_column))
+        .into_iter()
+        .chain(
+            columns
+                .iter()
+                .filter(|column| !keep_columns.contains(&Some(column.as_str())))
+                .map(|column| {
+                    let quoted = field_to_sql_quote(column);
+                    match Some(column.as_str()) == version {
+                        true => format!("{} = {} + 1", quoted, quoted),
+                        false => format!("{} = VALUES({})", quoted, quoted),
+                    }
+                }),
+        )
+        .collect::<Vec<_>>();
+    format!(
+        "INSERT INTO {} ({}, {}) VALUES(?, {}) ON DUPLICATE KEY UPDATE {}",
+        table_name,
+        id_column,
+        columns
+            .iter()
+            .map(|column| field_to_sql_quote(column))
+            .collect::<Vec<_>>()
+            .join(", "),
+        vec!["?"; columns.len()].join(", "),
+        assignment_vec.join(", ")
+    )
+}
+
+/// `upsert`之前锁定冲突数据的语句，与`ON DUPLICATE KEY UPDATE`相同，按照主键和每个唯一索引查找
+///
+/// 参数依次为主键、每个唯一索引的字段
+fn get_upsert_lock_sql(table_name: &str, id: &str, unique_indexes: &[Vec<String>]) -> String {
+    let condition_vec = std::iter::once(format!("{} = ?", field_to_sql_quote(id)))
+        .chain(unique_indexes.iter().map(|columns| {
+            format!(
+                "({})",
+                columns
+                    .iter()
+                    .map(|column| format!("{} = ?", field_to_sql_quote(column)))
+                    .collect::<Vec<_>>()
+                    .join(" AND ")
+            )
+        }))
+        .collect::<Vec<_>>();
+    format!(
+        "SELECT {} FROM {} WHERE {} FOR UPDATE",
+        field_to_sql_quote(id),
+        table_name,
+        condition_vec.join(" OR ")
+    )
+}
+
 fn field_to_sql_quote(field: &str) -> String {
     format!("`{}`", field)
 }
@@ -430,7 +1642,7 @@ fn field_to_sql_quote(field: &str) -> String {
 fn fields_to_bind_quote(struct_ident: &Ident, fields: &Vec<&Field>) -> Vec<TokenStream2> {
     fields
         .iter()
-        .map(|field| field_to_bind_quote(&struct_ident, field))
+        .map(|field| field_to_bind_quote(struct_ident, field))
         .collect()
 }
 
@@ -463,7 +1675,8 @@ fn field_to_bind_quote(struct_ident: &Ident, field: &Field) -> TokenStream2 {
                     quote!(#struct_ident.#field_var_name)
                 }
             }
-            _ => quote!(),
+            //带路径或者泛型参数的类型，例如`rust_decimal::Decimal`、`Vec<u8>`，通过引用绑定
+            _ => quote!(&#struct_ident.#field_var_name),
         }
     };
     quote!(
@@ -473,26 +1686,23 @@ fn field_to_bind_quote(struct_ident: &Ident, field: &Field) -> TokenStream2 {
 
 /// 判断是否public字段
 fn is_vis_public_crate(vis: &Visibility) -> bool {
-    match vis {
-        Visibility::Public(_) | Visibility::Crate(_) => true,
-        _ => false,
-    }
+    matches!(vis, Visibility::Public(_) | Visibility::Crate(_))
 }
 /// 判断是否为基础类型，非struct类型
 fn is_base_type(field: &Field) -> bool {
-    match (&field.ty, &field.ident) {
-        (syn::Type::Path(_), Some(_)) => true,
-        _ => false,
-    }
+    matches!((&field.ty, &field.ident), (syn::Type::Path(_), Some(_)))
 }
 
 /// 判断字段属性是否存在
-fn field_attr_exists(field: &Field, attr_name: &str) -> bool {
+pub(crate) fn field_attr_exists(field: &Field, attr_name: &str) -> bool {
     get_field_attr(field, attr_name).is_some()
 }
 
 /// 根据属性名字获取字段的属性对象
-fn get_field_attr<'a>(field: &'a Field, attr_name: &str) -> Option<(&'a Field, &'a Attribute)> {
+pub(crate) fn get_field_attr<'a>(
+    field: &'a Field,
+    attr_name: &str,
+) -> Option<(&'a Field, &'a Attribute)> {
     for attribute in field.attrs.iter() {
         if attribute.path.is_ident(attr_name) {
             return Some((field, attribute));
@@ -514,8 +1724,75 @@ fn get_ident(fields: &Fields, ident_name: &str) -> Ident {
     format_ident!("{}", ident_name)
 }
 
+/// 获取`create_time`、`update_time`字段，优先使用带有属性的字段，其次使用同名字段，都不存在时返回`None`
+fn get_time_field<'a>(fields: &'a Fields, time_name: &str) -> Option<&'a Field> {
+    fields
+        .iter()
+        .find(|field| field_attr_exists(field, time_name))
+        .or_else(|| {
+            fields
+                .iter()
+                .find(|field| field.ident.as_ref().is_some_and(|ident| ident == time_name))
+        })
+}
+
+/// 根据属性名字获取字段，不存在时返回`None`
+fn get_attr_field<'a>(fields: &'a Fields, attr_name: &str) -> Option<&'a Field> {
+    fields
+        .iter()
+        .find(|field| field_attr_exists(field, attr_name))
+}
+
+/// 确定`save_or_update`判断新数据的方式
+///
+/// 没有指定时，`Option`主键使用`option`，整数主键使用`sentinel`，其他类型的主键使用`exists`
+fn get_new_record(new_record: Option<NewRecord>, id_ty: &syn::Type) -> syn::Result<NewRecord> {
+    let id_inner_ty = extract_type_from_option(id_ty);
+    let integer_id = is_integer_type(id_inner_ty.unwrap_or(id_ty));
+    match new_record {
+        None if id_inner_ty.is_some() => Ok(NewRecord::Option),
+        None if integer_id => Ok(NewRecord::Sentinel),
+        None => Ok(NewRecord::Exists),
+        Some(NewRecord::Sentinel) if id_inner_ty.is_some() || !integer_id => {
+            Err(syn::Error::new_spanned(
+                id_ty,
+                "new_record = \"sentinel\" requires an integer id [sql_helper]",
+            ))
+        }
+        Some(NewRecord::Option) if id_inner_ty.is_none() || !integer_id => {
+            Err(syn::Error::new_spanned(
+                id_ty,
+                "new_record = \"option\" requires an Option<integer> id [sql_helper]",
+            ))
+        }
+        Some(new_record) => Ok(new_record),
+    }
+}
+
+/// 是否为整数类型
+fn is_integer_type(ty: &syn::Type) -> bool {
+    let syn::Type::Path(type_path) = ty else {
+        return false;
+    };
+    type_path.path.get_ident().is_some_and(|ident| {
+        matches!(
+            ident.to_string().as_str(),
+            "i8" | "u8" | "i16" | "u16" | "i32" | "u32" | "i64" | "u64" | "isize" | "usize"
+        )
+    })
+}
+
+/// 获取主键字段的类型，没有找到主键字段时默认为`i32`
+fn get_id_type(fields: &Fields, id: &Ident) -> syn::Type {
+    fields
+        .iter()
+        .find(|field| field.ident.as_ref() == Some(id))
+        .map(|field| field.ty.clone())
+        .unwrap_or_else(|| syn::parse_quote!(i32))
+}
+
 /// 获取表字段名字
-fn get_table_field_name(field: &Field) -> String {
+pub(crate) fn get_table_field_name(field: &Field) -> String {
     if let Some((_, attr)) = get_field_attr(field, "field_name") {
         // for attribute in field.attrs.iter() {
         //     eprintln!("field attribute: {}", attribute.tokens);
@@ -533,32 +1810,74 @@ fn get_table_field_name(field: &Field) -> String {
 
 fn get_auto_time_quote(
     struct_ident: &Ident,
-    create_time: Option<&Ident>,
-    update_time: &Ident,
+    create_time: Option<&Field>,
+    update_time: Option<&Field>,
 ) -> TokenStream2 {
     let create_time_quote = if let Some(ct) = create_time {
         get_update_time_quote(struct_ident, ct)
     } else {
         quote!()
     };
-    let update_time_quote = get_update_time_quote(struct_ident, update_time);
+    let update_time_quote = if let Some(ut) = update_time {
+        get_update_time_quote(struct_ident, ut)
+    } else {
+        quote!()
+    };
     quote!(
         #create_time_quote
         #update_time_quote
     )
 }
 
-fn get_update_time_quote(struct_ident: &Ident, time_ident: &Ident) -> TokenStream2 {
+fn get_update_time_quote(struct_ident: &Ident, time_field: &Field) -> TokenStream2 {
+    let Some(time_ident) = &time_field.ident else {
+        return quote!();
+    };
+    let now_quote = get_now_quote(time_field);
     quote!(
         //if #struct_ident.#time_ident == Default::default() {
-            #struct_ident.#time_ident = chrono::Local::now().naive_local();
+            #struct_ident.#time_ident = #now_quote;
         //}
     )
 }
 
+/// 当前时间，`Option<NaiveDateTime>`字段为`Some(...)`
+fn get_now_quote(time_field: &Field) -> TokenStream2 {
+    match extract_type_from_option(&time_field.ty) {
+        Some(_) => quote!(Some(chrono::Local::now().naive_local())),
+        None => quote!(chrono::Local::now().naive_local()),
+    }
+}
+
+/// 从审计上下文中获取当前操作人，并赋值给`created_by`、`updated_by`字段
+fn get_audit_quote(
+    struct_ident: &Ident,
+    created_by: Option<&Field>,
+    updated_by: Option<&Field>,
+) -> TokenStream2 {
+    let audit_quote_vec = [created_by, updated_by]
+        .into_iter()
+        .flatten()
+        .filter_map(|field| {
+            let field_ident = field.ident.as_ref()?;
+            let actor_quote = if extract_type_from_option(&field.ty).is_some() {
+                quote!(Some(actor))
+            } else {
+                quote!(actor)
+            };
+            Some(quote!(
+                if let Some(actor) = sqlx_sqlhelper::current_actor() {
+                    #struct_ident.#field_ident = #actor_quote;
+                }
+            ))
+        })
+        .collect::<Vec<_>>();
+    quote!(#(#audit_quote_vec)*)
+}
+
 /// 获取type是否为Option类型
 /// 代码来自于：https://stackoverflow.com/a/56264023
-fn extract_type_from_option(ty: &syn::Type) -> Option<&syn::Type> {
+pub(crate) fn extract_type_from_option(ty: &syn::Type) -> Option<&syn::Type> {
     use syn::{GenericArgument, Path, PathArguments, PathSegment};
 
     fn extract_type_path(ty: &syn::Type) -> Option<&Path> {
@@ -571,18 +1890,14 @@ fn extract_type_from_option(ty: &syn::Type) -> Option<&syn::Type> {
     // TODO store (with lazy static) the vec of string
     // TODO maybe optimization, reverse the order of segments
     fn extract_option_segment(path: &Path) -> Option<&PathSegment> {
-        let idents_of_path = path
-            .segments
-            .iter()
-            .into_iter()
-            .fold(String::new(), |mut acc, v| {
-                acc.push_str(&v.ident.to_string());
-                acc.push('|');
-                acc
-            });
+        let idents_of_path = path.segments.iter().fold(String::new(), |mut acc, v| {
+            acc.push_str(&v.ident.to_string());
+            acc.push('|');
+            acc
+        });
         vec!["Option|", "std|option|Option|", "core|option|Option|"]
             .into_iter()
-            .find(|s| &idents_of_path == *s)
+            .find(|s| idents_of_path == *s)
             .and_then(|_| path.segments.last())
     }
 
@@ -601,3 +1916,46 @@ fn extract_type_from_option(ty: &syn::Type) -> Option<&syn::Type> {
             _ => None,
         })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{get_upsert_lock_sql, get_upsert_sql};
+
+    #[test]
+    fn upsert_sql() {
+        let columns = ["name", "create_time", "version"].map(String::from);
+        assert_eq!(
+            get_upsert_sql(
+                "`user`",
+                "id",
+                &columns,
+                true,
+                &[Some("create_time"), None],
+                Some("version"),
+            ),
+            "INSERT INTO `user` (`id`, `name`, `create_time`, `version`) VALUES(?, ?, ?, ?) \
+             ON DUPLICATE KEY UPDATE `id` = LAST_INSERT_ID(`id`), `name` = VALUES(`name`), `version` = `version` + 1"
+        );
+        //非整数主键不使用`LAST_INSERT_ID`
+        assert_eq!(
+            get_upsert_sql("`api_key`", "id", &columns[..1], false, &[], None),
+            "INSERT INTO `api_key` (`id`, `name`) VALUES(?, ?) ON DUPLICATE KEY UPDATE `name` = VALUES(`name`)"
+        );
+    }
+
+    #[test]
+    fn upsert_lock_sql() {
+        assert_eq!(
+            get_upsert_lock_sql("`user`", "id", &[]),
+            "SELECT `id` FROM `user` WHERE `id` = ? FOR UPDATE"
+        );
+        let unique_indexes = [
+            vec!["email".to_string()],
+            vec!["tenant_id".to_string(), "account".to_string()],
+        ];
+        assert_eq!(
+            get_upsert_lock_sql("`user`", "id", &unique_indexes),
+            "SELECT `id` FROM `user` WHERE `id` = ? OR (`email` = ?) OR (`tenant_id` = ? AND `account` = ?) FOR UPDATE"
+        );
+    }
+}
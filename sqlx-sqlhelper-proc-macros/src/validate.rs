@@ -0,0 +1,262 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parenthesized,
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    Error, Expr, Field, Ident, LitStr, Path, Result, Token,
+};
+
+use crate::sql_helper::extract_type_from_option;
+
+const VALIDATE_ATTR: &str = "validate";
+
+/// `#[validate(...)]`中的校验规则
+enum Rule {
+    /// `length(min = 1, max = 20)`
+    Length(Option<Expr>, Option<Expr>),
+    /// `range(min = 0, max = 150)`
+    Range(Option<Expr>, Option<Expr>),
+    /// `regex = "^[a-z]+$"`
+    Regex(LitStr),
+    /// `custom = "path::to::fn"`或者`custom = path::to::fn`
+    Custom(Path),
+}
+
+impl Parse for Rule {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let ident: Ident = input.parse()?;
+        match ident.to_string().as_str() {
+            "length" | "range" => {
+                let content;
+                parenthesized!(content in input);
+                let (mut min, mut max) = (None, None);
+                for arg in Punctuated::<BoundArg, Token![,]>::parse_terminated(&content)? {
+                    match arg.name.to_string().as_str() {
+                        "min" => min = Some(arg.value),
+                        "max" => max = Some(arg.value),
+                        _ => {
+                            return Err(Error::new(
+                                arg.name.span(),
+                                "expected `min` or `max` [validate]",
+                            ))
+                        }
+                    }
+                }
+                match ident == "length" {
+                    true => Ok(Rule::Length(min, max)),
+                    false => Ok(Rule::Range(min, max)),
+                }
+            }
+            "regex" => {
+                input.parse::<Token![=]>()?;
+                let pattern: LitStr = input.parse()?;
+                //编译期检查正则表达式，避免运行时才发现错误
+                if let Err(e) = regex::Regex::new(&pattern.value()) {
+                    return Err(Error::new(
+                        pattern.span(),
+                        format!("invalid regex: {} [validate]", e),
+                    ));
+                }
+                Ok(Rule::Regex(pattern))
+            }
+            "custom" => {
+                input.parse::<Token![=]>()?;
+                let path = match input.peek(LitStr) {
+                    true => input.parse::<LitStr>()?.parse()?,
+                    false => input.parse()?,
+                };
+                Ok(Rule::Custom(path))
+            }
+            _ => Err(Error::new(
+                ident.span(),
+                format!("unsupported rule `{}` [validate]", ident),
+            )),
+        }
+    }
+}
+
+/// `min = ...`、`max = ...`
+struct BoundArg {
+    name: Ident,
+    value: Expr,
+}
+
+impl Parse for BoundArg {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let name = input.parse()?;
+        input.parse::<Token![=]>()?;
+        Ok(Self {
+            name,
+            value: input.parse()?,
+        })
+    }
+}
+
+/// 生成`validate`函数，根据字段上的`#[validate(...)]`属性校验数据
+pub fn get_validate_fn(fields: &[&Field]) -> Result<TokenStream2> {
+    let mut field_check_vec = Vec::new();
+    for field in fields {
+        let Some(field_ident) = &field.ident else {
+            continue;
+        };
+        let field_name = field_ident.to_string();
+        let ty = extract_type_from_option(&field.ty).unwrap_or(&field.ty);
+        //`String`字段的自定义函数接收`&str`
+        let is_string =
+            matches!(ty, syn::Type::Path(type_path) if type_path.path.is_ident("String"));
+        let mut check_vec = Vec::new();
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident(VALIDATE_ATTR))
+        {
+            let rules = attr.parse_args_with(Punctuated::<Rule, Token![,]>::parse_terminated)?;
+            for rule in rules {
+                check_vec.push(get_rule_quote(&field_name, is_string, rule));
+            }
+        }
+        if check_vec.is_empty() {
+            continue;
+        }
+        let field_check = if extract_type_from_option(&field.ty).is_some() {
+            quote!(
+                if let Some(value) = self.#field_ident.as_ref() {
+                    #(#check_vec)*
+                }
+            )
+        } else {
+            quote!({
+                let value = &self.#field_ident;
+                #(#check_vec)*
+            })
+        };
+        field_check_vec.push(field_check);
+    }
+
+    let body = if field_check_vec.is_empty() {
+        quote!(Ok(()))
+    } else {
+        quote!(
+            let mut errors = sqlx_sqlhelper::ValidationErrors::default();
+            #(#field_check_vec)*
+            errors.into_result()
+        )
+    };
+    Ok(quote!(
+        /// 根据字段上的`#[validate(...)]`属性校验数据，返回所有校验失败的字段
+        pub fn validate(&self) -> Result<(), sqlx_sqlhelper::ValidationErrors> {
+            #body
+        }
+    ))
+}
+
+fn get_rule_quote(field_name: &str, is_string: bool, rule: Rule) -> TokenStream2 {
+    let option_quote = |expr: Option<Expr>| match expr {
+        Some(expr) => quote!(Some(#expr)),
+        None => quote!(None),
+    };
+    match rule {
+        Rule::Length(min, max) => {
+            let (min, max) = (option_quote(min), option_quote(max));
+            quote!(sqlx_sqlhelper::validate::length(&mut errors, #field_name, value, #min, #max);)
+        }
+        Rule::Range(min, max) => {
+            let (min, max) = (option_quote(min), option_quote(max));
+            quote!(sqlx_sqlhelper::validate::range(&mut errors, #field_name, value, #min, #max);)
+        }
+        Rule::Regex(pattern) => quote!({
+            static REGEX: ::std::sync::OnceLock<sqlx_sqlhelper::validate::Regex> =
+                ::std::sync::OnceLock::new();
+            sqlx_sqlhelper::validate::regex(&mut errors, #field_name, value, &REGEX, #pattern);
+        }),
+        Rule::Custom(path) => {
+            let value = match is_string {
+                true => quote!(value.as_str()),
+                false => quote!(value),
+            };
+            quote!(sqlx_sqlhelper::validate::custom(&mut errors, #field_name, #value, #path);)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::{parse_quote, Data, DeriveInput, Fields};
+
+    use super::*;
+
+    fn validate_fn(input: DeriveInput) -> Result<String> {
+        let Data::Struct(data) = &input.data else {
+            unreachable!()
+        };
+        let Fields::Named(fields) = &data.fields else {
+            unreachable!()
+        };
+        let fields = fields.named.iter().collect::<Vec<_>>();
+        get_validate_fn(&fields).map(|tokens| tokens.to_string())
+    }
+
+    #[test]
+    fn invalid_regex() {
+        let error = validate_fn(parse_quote! {
+            struct User {
+                #[validate(regex = "^[a-z+$")]
+                account: String,
+            }
+        })
+        .err()
+        .unwrap();
+        assert!(error.to_string().starts_with("invalid regex: "));
+        assert!(error.to_string().ends_with("[validate]"));
+    }
+
+    #[test]
+    fn unsupported_rule() {
+        let error = validate_fn(parse_quote! {
+            struct User {
+                #[validate(length(min = 1, size = 2))]
+                account: String,
+            }
+        })
+        .err()
+        .unwrap();
+        assert_eq!(error.to_string(), "expected `min` or `max` [validate]");
+        let error = validate_fn(parse_quote! {
+            struct User {
+                #[validate(email)]
+                email: String,
+            }
+        })
+        .err()
+        .unwrap();
+        assert_eq!(error.to_string(), "unsupported rule `email` [validate]");
+    }
+
+    #[test]
+    fn custom_string_as_str() {
+        let tokens = validate_fn(parse_quote! {
+            struct User {
+                #[validate(custom = "not_admin")]
+                account: String,
+                #[validate(custom = check_nick)]
+                nick: Option<String>,
+                #[validate(custom = "check_age")]
+                age: u8,
+            }
+        })
+        .unwrap();
+        assert!(tokens.contains(
+            &quote!(sqlx_sqlhelper::validate::custom(&mut errors, "account", value.as_str(), not_admin);)
+                .to_string()
+        ));
+        assert!(tokens.contains(
+            &quote!(sqlx_sqlhelper::validate::custom(&mut errors, "nick", value.as_str(), check_nick);)
+                .to_string()
+        ));
+        assert!(tokens.contains(
+            &quote!(sqlx_sqlhelper::validate::custom(&mut errors, "age", value, check_age);)
+                .to_string()
+        ));
+    }
+}
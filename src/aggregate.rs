@@ -0,0 +1,115 @@
+use sqlx::{mysql::MySqlArguments, Decode, Executor, MySql, Type};
+
+use crate::{Crud, Result};
+
+/// 聚合函数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate {
+    Sum,
+    Min,
+    Max,
+    Avg,
+}
+
+impl Aggregate {
+    fn sql(&self) -> &'static str {
+        match self {
+            Aggregate::Sum => "SUM",
+            Aggregate::Min => "MIN",
+            Aggregate::Max => "MAX",
+            Aggregate::Avg => "AVG",
+        }
+    }
+}
+
+/// 校验字段是否为模型的表字段，也可以传入`#[field_name]`字段的struct字段名，返回表字段名
+pub fn resolve_column<T: Crud>(column: &str) -> Result<&'static str> {
+    T::META.resolve_column(column)
+}
+
+/// 查询满足条件的数量，`where_sql`与`list_by`相同，例如`AND age > ?`
+pub async fn count<'e, T, E>(executor: E, where_sql: &str, args: MySqlArguments) -> Result<i64>
+where
+    T: Crud,
+    E: Executor<'e, Database = MySql>,
+{
+    let sql = format!(
+        "SELECT COUNT(*) FROM `{}` WHERE 1=1 {}",
+        T::TABLE,
+        where_sql
+    );
+    Ok(sqlx::query_scalar_with::<_, i64, _>(&sql, args)
+        .fetch_one(executor)
+        .await?)
+}
+
+/// 是否存在满足条件的数据，使用`SELECT 1 ... LIMIT 1`，不会扫描所有匹配的数据
+pub async fn exists<'e, T, E>(executor: E, where_sql: &str, args: MySqlArguments) -> Result<bool>
+where
+    T: Crud,
+    E: Executor<'e, Database = MySql>,
+{
+    let sql = format!(
+        "SELECT 1 FROM `{}` WHERE 1=1 {} LIMIT 1",
+        T::TABLE,
+        where_sql
+    );
+    Ok(sqlx::query_with(&sql, args)
+        .fetch_optional(executor)
+        .await?
+        .is_some())
+}
+
+/// 对字段执行聚合函数，没有数据时返回`None`
+///
+/// `V`需要与MySQL返回的类型一致，不一致时sqlx解码失败返回错误，不会丢失精度：
+/// `MIN`、`MAX`返回字段本身的类型；整数和`DECIMAL`字段的`SUM`、`AVG`返回`DECIMAL`，
+/// 使用`Decimal`（开启`rust_decimal` feature）；浮点数字段的`SUM`、`AVG`返回`f64`
+pub async fn aggregate<'e, T, V, E>(
+    executor: E,
+    function: Aggregate,
+    column: &str,
+    where_sql: &str,
+    args: MySqlArguments,
+) -> Result<Option<V>>
+where
+    T: Crud,
+    V: for<'r> Decode<'r, MySql> + Type<MySql> + Send + Unpin,
+    E: Executor<'e, Database = MySql>,
+{
+    let sql = format!(
+        "SELECT {}(`{}`) FROM `{}` WHERE 1=1 {}",
+        function.sql(),
+        resolve_column::<T>(column)?,
+        T::TABLE,
+        where_sql
+    );
+    Ok(sqlx::query_scalar_with::<_, Option<V>, _>(&sql, args)
+        .fetch_one(executor)
+        .await?)
+}
+
+/// 按照字段分组统计数量，按照数量从大到小排序，可空字段需要使用`Option<V>`
+pub async fn group_count<'e, T, V, E>(
+    executor: E,
+    column: &str,
+    where_sql: &str,
+    args: MySqlArguments,
+) -> Result<Vec<(V, i64)>>
+where
+    T: Crud,
+    V: for<'r> Decode<'r, MySql> + Type<MySql> + Send + Unpin,
+    E: Executor<'e, Database = MySql>,
+{
+    let column = resolve_column::<T>(column)?;
+    let sql = format!(
+        "SELECT `{}`, COUNT(*) AS `count` FROM `{}` WHERE 1=1 {} GROUP BY `{}` ORDER BY `count` DESC",
+        column,
+        T::TABLE,
+        where_sql,
+        column
+    );
+    Ok(sqlx::query_as_with::<_, (V, i64), _>(&sql, args)
+        .fetch_all(executor)
+        .await?)
+}
@@ -0,0 +1,43 @@
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// 审计上下文，为`#[created_by]`、`#[updated_by]`字段提供当前操作人
+///
+/// 一般由鉴权中间件写入，例如使用`tokio::task_local!`保存当前请求的用户。
+///
+/// # Examples
+///
+/// ```ignore
+/// tokio::task_local! {
+///     pub static CURRENT_USER: String;
+/// }
+///
+/// sqlx_sqlhelper::set_audit_context(|| CURRENT_USER.try_with(|u| u.clone()).ok());
+/// ```
+pub trait AuditContext: Send + Sync + 'static {
+    /// 获取当前操作人，没有操作人时返回`None`
+    fn current_actor(&self) -> Option<String>;
+}
+
+impl<F> AuditContext for F
+where
+    F: Fn() -> Option<String> + Send + Sync + 'static,
+{
+    fn current_actor(&self) -> Option<String> {
+        self()
+    }
+}
+
+static AUDIT_CONTEXT: OnceLock<Box<dyn AuditContext>> = OnceLock::new();
+
+/// 设置全局的审计上下文，只能设置一次，重复设置时返回`false`
+pub fn set_audit_context<C: AuditContext>(context: C) -> bool {
+    AUDIT_CONTEXT.set(Box::new(context)).is_ok()
+}
+
+/// 获取当前操作人，并转换为审计字段的类型
+///
+/// 未设置审计上下文、没有操作人或者类型转换失败时返回`None`
+pub fn current_actor<T: FromStr>() -> Option<T> {
+    AUDIT_CONTEXT.get()?.current_actor()?.parse().ok()
+}
@@ -0,0 +1,473 @@
+use sqlx::{error::BoxDynError, mysql::MySqlArguments, Arguments, Encode, Executor, MySql, Type};
+
+use crate::{Crud, Error, Result, TableMeta, ValidationErrors};
+
+/// 延迟绑定的参数，`SET`的参数需要绑定在`WHERE`的参数之前
+type Bind = Box<dyn FnOnce(&mut MySqlArguments) -> Result<(), BoxDynError> + Send>;
+
+fn bind<V>(value: V) -> Bind
+where
+    V: for<'q> Encode<'q, MySql> + Type<MySql> + Send + 'static,
+{
+    Box::new(move |args: &mut MySqlArguments| args.add(value))
+}
+
+fn apply(binds: Vec<Bind>, args: &mut MySqlArguments) -> Result<()> {
+    for bind in binds {
+        bind(args).map_err(|e| Error::Bind(e.to_string()))?;
+    }
+    Ok(())
+}
+
+/// `update_where`、`delete_where`的`WHERE`条件
+///
+/// 条件为空时会修改或删除整张表，默认拒绝执行并返回`Error::EmptyFilter`，
+/// 需要通过`allow_full_table`显式允许。
+///
+/// # Examples
+///
+/// ```ignore
+/// let filter = Filter::new("expire_time < ? AND status = ?")
+///     .bind(chrono::Local::now().naive_local())
+///     .bind(0);
+/// ```
+#[derive(Default)]
+pub struct Filter {
+    where_sql: String,
+    binds: Vec<Bind>,
+    allow_full_table: bool,
+}
+
+impl Filter {
+    /// `where_sql`不包含`WHERE`关键字，参数通过`bind`按顺序绑定
+    pub fn new(where_sql: impl Into<String>) -> Self {
+        Self {
+            where_sql: where_sql.into(),
+            ..Default::default()
+        }
+    }
+
+    /// 没有条件，需要配合`allow_full_table`使用
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    /// 绑定参数
+    pub fn bind<V>(mut self, value: V) -> Self
+    where
+        V: for<'q> Encode<'q, MySql> + Type<MySql> + Send + 'static,
+    {
+        self.binds.push(bind(value));
+        self
+    }
+
+    /// 允许条件为空时修改或删除整张表
+    pub fn allow_full_table(mut self) -> Self {
+        self.allow_full_table = true;
+        self
+    }
+
+    /// 条件是否为空
+    pub fn is_empty(&self) -> bool {
+        self.where_sql.trim().is_empty()
+    }
+
+    /// `WHERE`子句，条件为空且没有`allow_full_table`时返回`Error::EmptyFilter`
+    fn where_clause(&self) -> Result<String> {
+        match (self.is_empty(), self.allow_full_table) {
+            (false, _) => Ok(format!(" WHERE {}", self.where_sql)),
+            (true, true) => Ok(String::new()),
+            (true, false) => Err(Error::EmptyFilter),
+        }
+    }
+}
+
+/// `update_where`的`SET`子句，字段名会根据`META`校验
+///
+/// # Examples
+///
+/// ```ignore
+/// let set = SetClause::new().set("status", 2).set_raw("retry", "`retry` + 1");
+/// ```
+#[derive(Default)]
+pub struct SetClause {
+    /// 字段名和原始sql表达式，表达式为`None`时使用绑定的参数
+    assignments: Vec<(String, Option<String>)>,
+    binds: Vec<Bind>,
+}
+
+impl SetClause {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `column = ?`，`column`可以是表字段名或者struct字段名
+    pub fn set<V>(mut self, column: impl Into<String>, value: V) -> Self
+    where
+        V: for<'q> Encode<'q, MySql> + Type<MySql> + Send + 'static,
+    {
+        self.assignments.push((column.into(), None));
+        self.binds.push(bind(value));
+        self
+    }
+
+    /// `column = <expr>`，`expr`原样写入sql，例如`` `retry` + 1 ``、`NOW()`
+    pub fn set_raw(mut self, column: impl Into<String>, expr: impl Into<String>) -> Self {
+        self.assignments.push((column.into(), Some(expr.into())));
+        self
+    }
+
+    /// 是否没有需要修改的字段
+    pub fn is_empty(&self) -> bool {
+        self.assignments.is_empty()
+    }
+
+    fn to_sql(&self, meta: &TableMeta) -> Result<String> {
+        self.assignments
+            .iter()
+            .map(|(column, expr)| {
+                let column = meta.resolve_column(column)?;
+                Ok(format!("`{}` = {}", column, expr.as_deref().unwrap_or("?")))
+            })
+            .collect::<Result<Vec<_>>>()
+            .map(|assignments| assignments.join(", "))
+    }
+}
+
+/// 批量修改满足条件的数据，返回受影响的行数
+///
+/// 不会调用`SqlHelperHooks`中的钩子，也不会写入历史表；`set`为空时不执行sql，直接返回0。
+/// 开启历史表的模型不会生成`update_where`，也不应直接调用该函数。
+pub async fn update_where<'e, T, E>(executor: E, filter: Filter, set: SetClause) -> Result<u64>
+where
+    T: Crud,
+    E: Executor<'e, Database = MySql>,
+{
+    let Some((sql, args)) = update_where_query(&T::META, filter, set)? else {
+        return Ok(0);
+    };
+    Ok(sqlx::query_with(&sql, args)
+        .execute(executor)
+        .await?
+        .rows_affected())
+}
+
+/// `update_where`的语句和参数，先绑定`SET`的参数再绑定`WHERE`的参数，`set`为空时返回`None`
+fn update_where_query(
+    meta: &TableMeta,
+    filter: Filter,
+    set: SetClause,
+) -> Result<Option<(String, MySqlArguments)>> {
+    let where_clause = filter.where_clause()?;
+    if set.is_empty() {
+        return Ok(None);
+    }
+    let sql = format!(
+        "UPDATE `{}` SET {}{}",
+        meta.table,
+        set.to_sql(meta)?,
+        where_clause
+    );
+    let mut args = MySqlArguments::default();
+    apply(set.binds, &mut args)?;
+    apply(filter.binds, &mut args)?;
+    Ok(Some((sql, args)))
+}
+
+/// 批量删除满足条件的数据，返回受影响的行数
+///
+/// 不会调用`SqlHelperHooks`中的钩子，也不会写入历史表。
+/// 开启历史表的模型不会生成`delete_where`，也不应直接调用该函数。
+pub async fn delete_where<'e, T, E>(executor: E, filter: Filter) -> Result<u64>
+where
+    T: Crud,
+    E: Executor<'e, Database = MySql>,
+{
+    let (sql, args) = delete_where_query(&T::META, filter)?;
+    Ok(sqlx::query_with(&sql, args)
+        .execute(executor)
+        .await?
+        .rows_affected())
+}
+
+/// `delete_where`的语句和参数
+fn delete_where_query(meta: &TableMeta, filter: Filter) -> Result<(String, MySqlArguments)> {
+    let sql = format!("DELETE FROM `{}`{}", meta.table, filter.where_clause()?);
+    let mut args = MySqlArguments::default();
+    apply(filter.binds, &mut args)?;
+    Ok((sql, args))
+}
+
+/// 单条语句最多绑定的参数数量
+const MAX_PLACEHOLDERS: usize = 65535;
+
+/// `update_many`每批最多修改的数据条数
+const UPDATE_MANY_CHUNK_SIZE: usize = 500;
+
+/// `update_many`中每条数据的执行结果，与传入的数据一一对应
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UpdateOutcome {
+    /// 已修改
+    Updated,
+    /// 数据不存在
+    NotFound,
+    /// `#[version]`版本号不一致
+    StaleVersion,
+    /// 主键与前面的数据重复，没有执行修改
+    Duplicate,
+    /// `#[validate(...)]`校验失败，没有执行修改
+    Invalid(ValidationErrors),
+}
+
+impl UpdateOutcome {
+    pub fn is_updated(&self) -> bool {
+        matches!(self, UpdateOutcome::Updated)
+    }
+}
+
+/// `update_many`每批修改的数据条数，保证绑定的参数数量不超过MySQL的限制
+pub fn update_many_chunk_size(columns: usize) -> usize {
+    (MAX_PLACEHOLDERS / (columns * 2 + 1)).clamp(1, UPDATE_MANY_CHUNK_SIZE)
+}
+
+fn placeholders(len: usize) -> String {
+    vec!["?"; len].join(", ")
+}
+
+/// 锁定本批数据的语句，用于区分数据不存在和版本号不一致
+pub fn update_many_lock_sql(table: &str, id: &str, version: Option<&str>, len: usize) -> String {
+    format!(
+        "SELECT `{}`{} FROM `{}` WHERE `{}` IN ({}) FOR UPDATE",
+        id,
+        version
+            .map(|version| format!(", `{}`", version))
+            .unwrap_or_default(),
+        table,
+        id,
+        placeholders(len)
+    )
+}
+
+/// 通过`CASE`一次修改多条数据的语句
+///
+/// 参数按照字段的顺序，每个字段依次绑定每条数据的主键和字段值，最后绑定`IN`中的主键。
+/// `version`字段由数据库自增。
+pub fn update_many_sql(
+    table: &str,
+    id: &str,
+    columns: &[&str],
+    version: Option<&str>,
+    len: usize,
+) -> String {
+    let when_sql = vec!["WHEN ? THEN ?"; len].join(" ");
+    let mut assignments = columns
+        .iter()
+        .map(|column| format!("`{}` = CASE `{}` {} END", column, id, when_sql))
+        .collect::<Vec<_>>();
+    if let Some(version) = version {
+        assignments.push(format!("`{}` = `{}` + 1", version, version));
+    }
+    format!(
+        "UPDATE `{}` SET {} WHERE `{}` IN ({})",
+        table,
+        assignments.join(", "),
+        id,
+        placeholders(len)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ColumnMeta;
+
+    const fn column(name: &'static str, field: &'static str) -> ColumnMeta {
+        ColumnMeta {
+            name,
+            field,
+            rust_type: "i32",
+            sql_type_hint: "INT",
+            nullable: false,
+            is_id: false,
+        }
+    }
+
+    const SESSION: TableMeta = TableMeta {
+        table: "session",
+        struct_name: "Session",
+        columns: &[
+            column("id", "id"),
+            column("status", "status"),
+            column("retryCount", "retry_count"),
+        ],
+        id: "id",
+        auto_increment: true,
+        create_time: None,
+        update_time: None,
+        created_by: None,
+        updated_by: None,
+        version: None,
+        indexes: &[],
+    };
+
+    /// 已绑定参数的编码，`u8`参数编码为一个字节，用于检查绑定顺序
+    fn encoded_values(args: &MySqlArguments) -> String {
+        let debug = format!("{:?}", args);
+        let start = debug.find("values: [").expect("values") + "values: [".len();
+        debug[start..start + debug[start..].find(']').expect("]")].to_string()
+    }
+
+    #[test]
+    fn empty_filter_rejected() {
+        let set = || SetClause::new().set("status", 2u8);
+        assert!(matches!(
+            update_where_query(&SESSION, Filter::all(), set()),
+            Err(Error::EmptyFilter)
+        ));
+        assert!(matches!(
+            update_where_query(&SESSION, Filter::new("  "), set()),
+            Err(Error::EmptyFilter)
+        ));
+        assert!(matches!(
+            delete_where_query(&SESSION, Filter::all()),
+            Err(Error::EmptyFilter)
+        ));
+        //`set`为空时同样先检查条件
+        assert!(matches!(
+            update_where_query(&SESSION, Filter::all(), SetClause::new()),
+            Err(Error::EmptyFilter)
+        ));
+    }
+
+    #[test]
+    fn allow_full_table() {
+        let (sql, args) = update_where_query(
+            &SESSION,
+            Filter::all().allow_full_table(),
+            SetClause::new().set("status", 2u8),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(sql, "UPDATE `session` SET `status` = ?");
+        assert_eq!(args.len(), 1);
+        let (sql, args) = delete_where_query(&SESSION, Filter::all().allow_full_table()).unwrap();
+        assert_eq!(sql, "DELETE FROM `session`");
+        assert_eq!(args.len(), 0);
+        //有条件时`allow_full_table`不影响语句
+        let (sql, _) =
+            delete_where_query(&SESSION, Filter::new("id = ?").bind(1u8).allow_full_table())
+                .unwrap();
+        assert_eq!(sql, "DELETE FROM `session` WHERE id = ?");
+    }
+
+    #[test]
+    fn unknown_set_column_rejected() {
+        let result = update_where_query(
+            &SESSION,
+            Filter::new("id = ?").bind(1u8),
+            SetClause::new().set("status", 2u8).set("missing", 3u8),
+        );
+        assert!(matches!(result, Err(Error::UnknownColumn(column)) if column == "missing"));
+        let result = update_where_query(
+            &SESSION,
+            Filter::new("id = ?"),
+            SetClause::new().set_raw("missing", "NOW()"),
+        );
+        assert!(matches!(result, Err(Error::UnknownColumn(_))));
+        //`set`为空时不执行sql
+        assert!(
+            update_where_query(&SESSION, Filter::new("id = ?"), SetClause::new())
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn update_where_sql_bind_order() {
+        let (sql, args) = update_where_query(
+            &SESSION,
+            Filter::new("status = ? AND id > ?").bind(3u8).bind(4u8),
+            SetClause::new()
+                .set("status", 1u8)
+                .set_raw("retry_count", "`retryCount` + 1")
+                .set("id", 2u8),
+        )
+        .unwrap()
+        .unwrap();
+        //struct字段名转换为表字段名
+        assert_eq!(
+            sql,
+            "UPDATE `session` SET `status` = ?, `retryCount` = `retryCount` + 1, `id` = ? \
+             WHERE status = ? AND id > ?"
+        );
+        //先绑定`SET`的参数，再绑定`WHERE`的参数
+        assert_eq!(args.len(), 4);
+        assert_eq!(encoded_values(&args), "1, 2, 3, 4");
+    }
+
+    fn placeholder_count(sql: &str) -> usize {
+        sql.matches('?').count()
+    }
+
+    #[test]
+    fn update_many_sql_placeholders() {
+        let sql = update_many_sql("user", "id", &["account", "email"], None, 3);
+        assert_eq!(
+            sql,
+            "UPDATE `user` SET \
+             `account` = CASE `id` WHEN ? THEN ? WHEN ? THEN ? WHEN ? THEN ? END, \
+             `email` = CASE `id` WHEN ? THEN ? WHEN ? THEN ? WHEN ? THEN ? END \
+             WHERE `id` IN (?, ?, ?)"
+        );
+        //每个字段每条数据绑定主键和字段值，最后绑定`IN`中的主键
+        assert_eq!(placeholder_count(&sql), 2 * 2 * 3 + 3);
+    }
+
+    #[test]
+    fn update_many_sql_version() {
+        let sql = update_many_sql("user", "id", &["account"], Some("version"), 2);
+        assert!(sql.contains(", `version` = `version` + 1 WHERE"));
+        //版本号由数据库自增，不需要绑定参数
+        assert_eq!(placeholder_count(&sql), 2 * 2 + 2);
+    }
+
+    #[test]
+    fn update_many_lock_sql_version() {
+        assert_eq!(
+            update_many_lock_sql("user", "id", Some("version"), 2),
+            "SELECT `id`, `version` FROM `user` WHERE `id` IN (?, ?) FOR UPDATE"
+        );
+        assert_eq!(
+            update_many_lock_sql("user", "id", None, 1),
+            "SELECT `id` FROM `user` WHERE `id` IN (?) FOR UPDATE"
+        );
+    }
+
+    #[test]
+    fn update_many_chunk_size_bind_limit() {
+        //字段少时每批最多500条
+        assert_eq!(update_many_chunk_size(1), UPDATE_MANY_CHUNK_SIZE);
+        //MySQL每张表最多4096个字段
+        for columns in [1, 10, 64, 65, 100, 1000, 4096] {
+            let chunk_size = update_many_chunk_size(columns);
+            let sql = update_many_sql("t", "id", &vec!["c"; columns], None, chunk_size);
+            assert!(
+                placeholder_count(&sql) <= MAX_PLACEHOLDERS,
+                "columns: {}",
+                columns
+            );
+            assert!(chunk_size >= 1);
+        }
+        //达到绑定参数上限时减少每批的条数
+        assert_eq!(update_many_chunk_size(100), MAX_PLACEHOLDERS / 201);
+        assert!(
+            placeholder_count(&update_many_sql(
+                "t",
+                "id",
+                &vec!["c"; 100],
+                None,
+                update_many_chunk_size(100) + 1
+            )) > MAX_PLACEHOLDERS
+        );
+    }
+}
@@ -0,0 +1,62 @@
+use std::future::Future;
+
+use sqlx::{
+    mysql::{MySqlArguments, MySqlRow},
+    FromRow,
+};
+
+use crate::{Result, TableMeta};
+
+/// `SqlHelper`派生宏自动实现的增删改查trait，用于编写泛型代码
+///
+/// 所有方法都委托给派生宏生成的同名方法。
+///
+/// # Examples
+///
+/// ```ignore
+/// async fn load<T: Crud>(id: T::Id) -> sqlx_sqlhelper::Result<T> {
+///     T::get_by_id(id).await
+/// }
+/// ```
+pub trait Crud: Sized + Send + Sync + Unpin + for<'r> FromRow<'r, MySqlRow> {
+    /// 主键类型
+    type Id: Clone + Send + Sync + 'static;
+
+    /// 表名
+    const TABLE: &'static str;
+
+    /// 所有字段名，第一个为主键
+    const COLUMNS: &'static [&'static str];
+
+    /// 表的元数据
+    const META: TableMeta;
+
+    /// 主键的值
+    fn id(&self) -> Self::Id;
+
+    /// 从带有前缀的字段中读取数据，例如`u__id`、`u__account`，用于`JOIN`查询
+    ///
+    /// 不是表字段的struct字段使用`Default::default()`
+    fn from_row_prefixed(row: &MySqlRow, prefix: &str) -> Result<Self, sqlx::Error>;
+
+    fn get_by_id(id: Self::Id) -> impl Future<Output = Result<Self>> + Send;
+
+    fn find_by_id(id: Self::Id) -> impl Future<Output = Result<Option<Self>>> + Send;
+
+    fn list() -> impl Future<Output = Result<Vec<Self>>> + Send;
+
+    fn list_by(
+        where_sql: &str,
+        args: MySqlArguments,
+    ) -> impl Future<Output = Result<Vec<Self>>> + Send;
+
+    fn insert(&mut self) -> impl Future<Output = Result<Self>> + Send;
+
+    fn update(&mut self) -> impl Future<Output = Result<()>> + Send;
+
+    fn save_or_update(&mut self) -> impl Future<Output = Result<()>> + Send;
+
+    fn delete(&self) -> impl Future<Output = Result<bool>> + Send;
+
+    fn delete_by_id(id: Self::Id) -> impl Future<Output = Result<bool>> + Send;
+}
@@ -0,0 +1,151 @@
+use std::fmt::{self, Display};
+
+use sqlx::mysql::MySqlDatabaseError;
+
+use crate::ValidationErrors;
+
+/// MySQL主键或唯一索引冲突的错误码：`ER_DUP_ENTRY`
+const ER_DUP_ENTRY: u16 = 1062;
+/// MySQL唯一索引冲突的错误码：`ER_DUP_ENTRY_WITH_KEY_NAME`
+const ER_DUP_ENTRY_WITH_KEY_NAME: u16 = 1586;
+
+/// `SqlHelper`生成的方法以及`sql_args!`等声明宏统一返回的错误
+#[derive(Debug)]
+pub enum Error {
+    /// 数据不存在
+    NotFound,
+    /// 期望只有一条数据，但是匹配到了多条
+    MultipleRows,
+    /// 主键或唯一索引冲突，内容为MySQL返回的错误信息
+    Conflict(String),
+    /// `#[version]`字段与数据库中的版本不一致，数据已经被其他人修改
+    StaleVersion,
+    /// `#[validate(...)]`校验失败
+    Validation(ValidationErrors),
+    /// 绑定sql参数失败
+    Bind(String),
+    /// 字段不是模型的表字段
+    UnknownColumn(String),
+    /// 批量修改、删除的条件为空，需要通过`Filter::allow_full_table`显式允许
+    EmptyFilter,
+    /// 其他数据库错误
+    Database(sqlx::Error),
+}
+
+/// `Result<T, sqlx_sqlhelper::Error>`
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl Error {
+    /// MySQL返回的错误码
+    pub fn mysql_error_number(&self) -> Option<u16> {
+        match self {
+            Error::Database(e) => mysql_error_number(e),
+            _ => None,
+        }
+    }
+}
+
+fn mysql_error_number(error: &sqlx::Error) -> Option<u16> {
+    error
+        .as_database_error()?
+        .try_downcast_ref::<MySqlDatabaseError>()
+        .map(|e| e.number())
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NotFound => write!(f, "row not found"),
+            Error::MultipleRows => write!(f, "multiple rows found"),
+            Error::Conflict(message) => write!(f, "conflict: {}", message),
+            Error::StaleVersion => write!(f, "stale version"),
+            Error::Validation(errors) => errors.fmt(f),
+            Error::Bind(message) => write!(f, "bind sql args error: {}", message),
+            Error::UnknownColumn(column) => write!(f, "unknown column: {}", column),
+            Error::EmptyFilter => write!(f, "refuse to update or delete the whole table"),
+            Error::Database(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Validation(errors) => Some(errors),
+            Error::Database(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(error: sqlx::Error) -> Self {
+        let number = mysql_error_number(&error);
+        from_error_number(error, number)
+    }
+}
+
+/// 根据MySQL错误码转换sqlx的错误，错误码单独传入方便测试
+fn from_error_number(error: sqlx::Error, number: Option<u16>) -> Error {
+    match number {
+        Some(ER_DUP_ENTRY | ER_DUP_ENTRY_WITH_KEY_NAME) => {
+            let message = match error.as_database_error() {
+                Some(e) => e.message().to_string(),
+                None => error.to_string(),
+            };
+            Error::Conflict(message)
+        }
+        _ => match error {
+            sqlx::Error::RowNotFound => Error::NotFound,
+            error => Error::Database(error),
+        },
+    }
+}
+
+impl From<ValidationErrors> for Error {
+    fn from(errors: ValidationErrors) -> Self {
+        Error::Validation(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn row_not_found() {
+        assert!(matches!(
+            Error::from(sqlx::Error::RowNotFound),
+            Error::NotFound
+        ));
+    }
+
+    #[test]
+    fn duplicate_entry() {
+        for number in [ER_DUP_ENTRY, ER_DUP_ENTRY_WITH_KEY_NAME] {
+            let error =
+                sqlx::Error::Protocol("Duplicate entry 'admin' for key 'uk_user_account'".into());
+            match from_error_number(error, Some(number)) {
+                Error::Conflict(message) => assert!(message.contains("Duplicate entry 'admin'")),
+                error => panic!("expected conflict, got {:?}", error),
+            }
+        }
+    }
+
+    #[test]
+    fn other_errors() {
+        //死锁等其他MySQL错误以及非MySQL错误保留原来的错误
+        assert!(matches!(
+            from_error_number(sqlx::Error::Protocol("deadlock".into()), Some(1213)),
+            Error::Database(sqlx::Error::Protocol(_))
+        ));
+        assert!(matches!(
+            Error::from(sqlx::Error::PoolTimedOut),
+            Error::Database(sqlx::Error::PoolTimedOut)
+        ));
+        assert_eq!(
+            Error::from(sqlx::Error::PoolTimedOut).mysql_error_number(),
+            None
+        );
+    }
+}
@@ -0,0 +1,56 @@
+use chrono::NaiveDateTime;
+use sqlx::{mysql::MySqlRow, FromRow, Row};
+
+/// 历史表中记录的操作类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryOp {
+    Update,
+    Delete,
+}
+
+impl HistoryOp {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HistoryOp::Update => "UPDATE",
+            HistoryOp::Delete => "DELETE",
+        }
+    }
+}
+
+/// `#[sql_helper(history)]`写入`<table>_history`表的一条历史记录
+///
+/// `row`为修改或删除之前的数据
+#[derive(Debug, Clone)]
+pub struct History<T> {
+    pub history_id: i64,
+    pub op: HistoryOp,
+    pub time: NaiveDateTime,
+    pub actor: Option<String>,
+    pub row: T,
+}
+
+impl<'r, T> FromRow<'r, MySqlRow> for History<T>
+where
+    T: FromRow<'r, MySqlRow>,
+{
+    fn from_row(row: &'r MySqlRow) -> Result<Self, sqlx::Error> {
+        let op: String = row.try_get("history_op")?;
+        let op = match op.as_str() {
+            "UPDATE" => HistoryOp::Update,
+            "DELETE" => HistoryOp::Delete,
+            _ => {
+                return Err(sqlx::Error::ColumnDecode {
+                    index: "history_op".to_string(),
+                    source: format!("unknown history op: {}", op).into(),
+                })
+            }
+        };
+        Ok(Self {
+            history_id: row.try_get("history_id")?,
+            op,
+            time: row.try_get("history_time")?,
+            actor: row.try_get("history_actor")?,
+            row: T::from_row(row)?,
+        })
+    }
+}
@@ -0,0 +1,60 @@
+use std::future::Future;
+
+use crate::Result;
+
+/// `SqlHelper`生成的写操作的生命周期钩子
+///
+/// 默认所有钩子都为空实现。`SqlHelper`会自动为struct实现该trait，
+/// 如果需要自定义钩子，在struct上添加`#[sql_helper(hooks)]`后手动实现。
+///
+/// `before_*`钩子返回错误时，会直接中止本次操作；`after_*`钩子返回错误时，
+/// 操作已经执行，`tran_*`方法可由调用方回滚事务。
+///
+/// # Examples
+///
+/// ```ignore
+/// #[common_fields]
+/// #[derive(sqlx::FromRow, Debug, SqlHelper)]
+/// #[sql_helper(hooks)]
+/// pub struct User {
+///     pub email: String,
+/// }
+///
+/// impl sqlx_sqlhelper::SqlHelperHooks for User {
+///     async fn before_insert(&mut self) -> sqlx_sqlhelper::Result<()> {
+///         self.email = self.email.trim().to_lowercase();
+///         Ok(())
+///     }
+/// }
+/// ```
+pub trait SqlHelperHooks {
+    /// `insert`、`tran_insert`执行之前调用
+    fn before_insert(&mut self) -> impl Future<Output = Result<()>> + Send {
+        async { Ok(()) }
+    }
+
+    /// `insert`、`tran_insert`执行之后调用，此时`id`已经赋值
+    fn after_insert(&mut self) -> impl Future<Output = Result<()>> + Send {
+        async { Ok(()) }
+    }
+
+    /// `update`、`tran_update`执行之前调用
+    fn before_update(&mut self) -> impl Future<Output = Result<()>> + Send {
+        async { Ok(()) }
+    }
+
+    /// `update`、`tran_update`执行之后调用
+    fn after_update(&mut self) -> impl Future<Output = Result<()>> + Send {
+        async { Ok(()) }
+    }
+
+    /// `delete`、`delete_by_id`执行之前调用
+    fn before_delete(&self) -> impl Future<Output = Result<()>> + Send {
+        async { Ok(()) }
+    }
+
+    /// `delete`、`delete_by_id`执行之后调用
+    fn after_delete(&self) -> impl Future<Output = Result<()>> + Send {
+        async { Ok(()) }
+    }
+}
@@ -0,0 +1,124 @@
+use sqlx::{
+    mysql::{MySqlArguments, MySqlRow},
+    MySql, Pool, Row, ValueRef,
+};
+
+use crate::{Crud, Result};
+
+/// `JOIN`查询中的一个模型，`T`或者`LEFT JOIN`时的`Option<T>`
+pub trait JoinPart: Sized {
+    /// 带有别名前缀的字段列表，例如``u.`id` AS `u__id` ``
+    fn select_columns(alias: &str) -> String;
+
+    /// 从带有别名前缀的字段中读取数据
+    fn from_join_row(row: &MySqlRow, alias: &str) -> Result<Self, sqlx::Error>;
+}
+
+fn join_prefix(alias: &str) -> String {
+    format!("{}__", alias)
+}
+
+impl<T: Crud> JoinPart for T {
+    fn select_columns(alias: &str) -> String {
+        let prefix = join_prefix(alias);
+        T::COLUMNS
+            .iter()
+            .map(|column| format!("{}.`{}` AS `{}{}`", alias, column, prefix, column))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn from_join_row(row: &MySqlRow, alias: &str) -> Result<Self, sqlx::Error> {
+        T::from_row_prefixed(row, &join_prefix(alias))
+    }
+}
+
+/// `LEFT JOIN`没有匹配时主键为`NULL`，返回`None`
+impl<T: Crud> JoinPart for Option<T> {
+    fn select_columns(alias: &str) -> String {
+        T::select_columns(alias)
+    }
+
+    fn from_join_row(row: &MySqlRow, alias: &str) -> Result<Self, sqlx::Error> {
+        let id_column = format!("{}{}", join_prefix(alias), T::META.id);
+        match row.try_get_raw(id_column.as_str())?.is_null() {
+            true => Ok(None),
+            false => T::from_join_row(row, alias).map(Some),
+        }
+    }
+}
+
+/// `JOIN`查询结果的元组，例如`(User, Order)`、`(User, Option<Order>, Product)`
+pub trait JoinRow: Sized {
+    /// 每个模型的表别名
+    type Aliases;
+
+    /// `SELECT`之后的字段列表
+    fn select_columns(aliases: &Self::Aliases) -> String;
+
+    fn from_join_row(row: &MySqlRow, aliases: &Self::Aliases) -> Result<Self, sqlx::Error>;
+}
+
+macro_rules! impl_join_row {
+    ($len:literal; $($part:ident => $index:tt),+) => {
+        impl<$($part: JoinPart),+> JoinRow for ($($part,)+) {
+            type Aliases = [&'static str; $len];
+
+            fn select_columns(aliases: &Self::Aliases) -> String {
+                [$($part::select_columns(aliases[$index])),+].join(", ")
+            }
+
+            fn from_join_row(row: &MySqlRow, aliases: &Self::Aliases) -> Result<Self, sqlx::Error> {
+                Ok(($($part::from_join_row(row, aliases[$index])?,)+))
+            }
+        }
+    };
+}
+
+impl_join_row!(2; A => 0, B => 1);
+impl_join_row!(3; A => 0, B => 1, C => 2);
+impl_join_row!(4; A => 0, B => 1, C => 2, D => 3);
+
+/// 生成`JOIN`查询语句，`from_sql`为`FROM`及之后的部分
+pub fn join_sql<J: JoinRow>(aliases: &J::Aliases, from_sql: &str) -> String {
+    format!("SELECT {} {}", J::select_columns(aliases), from_sql)
+}
+
+/// 执行`JOIN`查询，每一行数据转换为模型的元组
+///
+/// 每个模型的字段都通过`<别名>__<字段名>`的形式查询，避免`id`、`create_time`等同名字段冲突。
+///
+/// # Examples
+///
+/// ```ignore
+/// let (sql, args) = sql_args!(
+///     "FROM `user` u JOIN `order` o ON o.user_id = u.id WHERE u.id = ?",
+///     user_id
+/// )?;
+/// let rows: Vec<(User, Order)> = sqlx_sqlhelper::join_all(&*db::POOL, &["u", "o"], sql, args).await?;
+/// ```
+pub async fn join_all<J: JoinRow>(
+    pool: &Pool<MySql>,
+    aliases: &J::Aliases,
+    from_sql: &str,
+    args: MySqlArguments,
+) -> Result<Vec<J>> {
+    let sql = join_sql::<J>(aliases, from_sql);
+    let rows = sqlx::query_with(&sql, args).fetch_all(pool).await?;
+    Ok(rows
+        .iter()
+        .map(|row| J::from_join_row(row, aliases))
+        .collect::<Result<Vec<_>, _>>()?)
+}
+
+/// 执行`JOIN`查询并返回第一行数据，没有数据时返回`None`，参考[`join_all`]
+pub async fn join_optional<J: JoinRow>(
+    pool: &Pool<MySql>,
+    aliases: &J::Aliases,
+    from_sql: &str,
+    args: MySqlArguments,
+) -> Result<Option<J>> {
+    let sql = join_sql::<J>(aliases, from_sql);
+    let row = sqlx::query_with(&sql, args).fetch_optional(pool).await?;
+    Ok(row.map(|row| J::from_join_row(&row, aliases)).transpose()?)
+}
@@ -1,2 +1,49 @@
 pub use sqlx_sqlhelper_proc_macros::*;
-pub mod sql_macros;
\ No newline at end of file
+pub mod sql_macros;
+
+mod audit;
+pub use audit::*;
+
+mod history;
+pub use history::*;
+
+mod hooks;
+pub use hooks::*;
+
+pub mod validate;
+pub use validate::{FieldError, ValidationErrors};
+
+mod error;
+pub use error::{Error, Result};
+
+mod transaction;
+pub use transaction::*;
+
+mod crud;
+pub use crud::*;
+
+mod meta;
+pub use meta::*;
+
+mod schema;
+pub use schema::*;
+
+mod migration;
+pub use migration::*;
+
+mod relation;
+pub use relation::*;
+
+mod join;
+pub use join::*;
+
+mod projection;
+pub use projection::*;
+
+mod aggregate;
+pub use aggregate::*;
+#[cfg(feature = "rust_decimal")]
+pub use rust_decimal::Decimal;
+
+mod bulk;
+pub use bulk::*;
@@ -0,0 +1,298 @@
+use crate::{Error, Result};
+
+/// 表的元数据，由`SqlHelper`派生宏在编译期生成，通过`User::META`访问
+///
+/// 可用于后台管理页面、数据导出等需要反射表结构的场景。
+///
+/// # Examples
+///
+/// ```ignore
+/// for column in User::META.columns {
+///     println!("{} -> {} {}", column.field, column.name, column.sql_type_hint);
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TableMeta {
+    /// 表名
+    pub table: &'static str,
+    /// 结构体名字
+    pub struct_name: &'static str,
+    /// 所有字段，第一个为主键
+    pub columns: &'static [ColumnMeta],
+    /// 主键字段名
+    pub id: &'static str,
+    /// 主键是否由数据库自增生成，`#[sql_helper(new_record = "exists")]`、`"upsert"`等由调用方赋值时为`false`
+    pub auto_increment: bool,
+    /// `#[create_time]`字段名
+    pub create_time: Option<&'static str>,
+    /// `#[update_time]`字段名
+    pub update_time: Option<&'static str>,
+    /// `#[created_by]`字段名
+    pub created_by: Option<&'static str>,
+    /// `#[updated_by]`字段名
+    pub updated_by: Option<&'static str>,
+    /// `#[version]`字段名
+    pub version: Option<&'static str>,
+    /// `#[index]`、`#[unique]`声明的索引
+    pub indexes: &'static [IndexMeta],
+}
+
+impl TableMeta {
+    /// 根据表字段名查找字段
+    pub fn column(&self, name: &str) -> Option<&'static ColumnMeta> {
+        self.columns.iter().find(|column| column.name == name)
+    }
+
+    /// 根据结构体字段名查找字段
+    pub fn column_by_field(&self, field: &str) -> Option<&'static ColumnMeta> {
+        self.columns.iter().find(|column| column.field == field)
+    }
+
+    /// 校验字段是否为表字段，也可以传入`#[field_name]`字段的struct字段名，返回表字段名
+    pub fn resolve_column(&self, column: &str) -> Result<&'static str> {
+        self.column(column)
+            .or_else(|| self.column_by_field(column))
+            .map(|column| column.name)
+            .ok_or_else(|| Error::UnknownColumn(column.to_string()))
+    }
+
+    /// 根据结构体字段名查找表字段名，可以在常量中使用，找不到时编译失败
+    ///
+    /// 用于`SqlProjection`在编译时检查投影字段
+    pub const fn field_column(&self, field: &str) -> &'static str {
+        let mut i = 0;
+        while i < self.columns.len() {
+            if str_eq(self.columns[i].field, field) {
+                return self.columns[i].name;
+            }
+            i += 1;
+        }
+        panic!("projection field is not a field of the table struct")
+    }
+
+    /// 主键字段
+    pub fn id_column(&self) -> Option<&'static ColumnMeta> {
+        self.columns.iter().find(|column| column.is_id)
+    }
+
+    /// 所有表字段名
+    pub fn column_names(&self) -> impl Iterator<Item = &'static str> {
+        self.columns.iter().map(|column| column.name)
+    }
+
+    /// 生成`CREATE TABLE`语句
+    ///
+    /// 主键由数据库自增生成时添加`AUTO_INCREMENT`，`create_time`默认为`CURRENT_TIMESTAMP`，
+    /// `update_time`默认为`CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP`
+    pub fn create_table_sql(&self) -> String {
+        self.build_create_table_sql(false)
+    }
+
+    /// 生成`CREATE TABLE IF NOT EXISTS`语句
+    pub fn create_table_if_not_exists_sql(&self) -> String {
+        self.build_create_table_sql(true)
+    }
+
+    fn build_create_table_sql(&self, if_not_exists: bool) -> String {
+        let mut definitions = self
+            .columns
+            .iter()
+            .map(|column| format!("  {}", self.column_definition(column)))
+            .collect::<Vec<_>>();
+        definitions.push(format!("  PRIMARY KEY (`{}`)", self.id));
+        definitions.extend(
+            self.indexes
+                .iter()
+                .map(|index| format!("  {}", index.key_definition())),
+        );
+        format!(
+            "CREATE TABLE {}`{}` (\n{}\n) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4",
+            if if_not_exists { "IF NOT EXISTS " } else { "" },
+            self.table,
+            definitions.join(",\n")
+        )
+    }
+
+    /// 建表语句中的字段定义，例如`` `name` VARCHAR(255) NOT NULL ``
+    pub fn column_definition(&self, column: &ColumnMeta) -> String {
+        let mut definition = format!(
+            "`{}` {} {}",
+            column.name,
+            column.sql_type_hint,
+            if column.nullable { "NULL" } else { "NOT NULL" }
+        );
+        let is_time = column.sql_type_hint.starts_with("DATETIME")
+            || column.sql_type_hint.starts_with("TIMESTAMP");
+        if column.is_id && self.auto_increment {
+            definition.push_str(" AUTO_INCREMENT");
+        } else if is_time && self.create_time == Some(column.name) {
+            definition.push_str(" DEFAULT CURRENT_TIMESTAMP");
+        } else if is_time && self.update_time == Some(column.name) {
+            definition.push_str(" DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP");
+        }
+        definition
+    }
+}
+
+/// 索引的元数据
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexMeta {
+    /// 索引名
+    pub name: &'static str,
+    /// 索引包含的表字段名
+    pub columns: &'static [&'static str],
+    /// 是否为唯一索引
+    pub unique: bool,
+}
+
+impl IndexMeta {
+    /// 建表语句中的索引定义，例如``UNIQUE KEY `uk_user_email` (`email`)``
+    pub fn key_definition(&self) -> String {
+        format!(
+            "{}KEY `{}` ({})",
+            if self.unique { "UNIQUE " } else { "" },
+            self.name,
+            self.quoted_columns()
+        )
+    }
+
+    /// 在已有的表上创建索引的语句，用于迁移
+    pub fn create_index_sql(&self, table: &str) -> String {
+        format!(
+            "CREATE {}INDEX `{}` ON `{}` ({})",
+            if self.unique { "UNIQUE " } else { "" },
+            self.name,
+            table,
+            self.quoted_columns()
+        )
+    }
+
+    /// 删除索引的语句，用于迁移
+    pub fn drop_index_sql(&self, table: &str) -> String {
+        format!("DROP INDEX `{}` ON `{}`", self.name, table)
+    }
+
+    fn quoted_columns(&self) -> String {
+        self.columns
+            .iter()
+            .map(|column| format!("`{}`", column))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// 表字段的元数据
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColumnMeta {
+    /// 表字段名，`#[field_name("...")]`指定时为指定的名字
+    pub name: &'static str,
+    /// 结构体字段名
+    pub field: &'static str,
+    /// 结构体字段的Rust类型，例如`Option<String>`
+    pub rust_type: &'static str,
+    /// MySQL字段类型，`#[column(type = "...")]`指定时为指定的类型，否则根据Rust类型推断，例如`VARCHAR(255)`
+    pub sql_type_hint: &'static str,
+    /// 是否可以为`NULL`，即字段类型为`Option<T>`
+    pub nullable: bool,
+    /// 是否为主键
+    pub is_id: bool,
+}
+
+const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const fn column(name: &'static str, sql_type_hint: &'static str, nullable: bool) -> ColumnMeta {
+        ColumnMeta {
+            name,
+            field: name,
+            rust_type: "",
+            sql_type_hint,
+            nullable,
+            is_id: false,
+        }
+    }
+
+    const ORDER: TableMeta = TableMeta {
+        table: "order",
+        struct_name: "Order",
+        columns: &[
+            ColumnMeta {
+                is_id: true,
+                ..column("id", "BIGINT", false)
+            },
+            column("account", "VARCHAR(255)", false),
+            column("remark", "TEXT", true),
+            column("price", "DECIMAL(12,4)", false),
+            column("token", "BINARY(16)", false),
+            column("create_time", "DATETIME", false),
+            column("update_time", "DATETIME", true),
+        ],
+        id: "id",
+        auto_increment: true,
+        create_time: Some("create_time"),
+        update_time: Some("update_time"),
+        created_by: None,
+        updated_by: None,
+        version: None,
+        indexes: &[],
+    };
+
+    #[test]
+    fn create_table_sql() {
+        assert_eq!(
+            ORDER.create_table_sql(),
+            "CREATE TABLE `order` (
+  `id` BIGINT NOT NULL AUTO_INCREMENT,
+  `account` VARCHAR(255) NOT NULL,
+  `remark` TEXT NULL,
+  `price` DECIMAL(12,4) NOT NULL,
+  `token` BINARY(16) NOT NULL,
+  `create_time` DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  `update_time` DATETIME NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+  PRIMARY KEY (`id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4"
+        );
+        assert!(ORDER
+            .create_table_if_not_exists_sql()
+            .starts_with("CREATE TABLE IF NOT EXISTS `order` (\n"));
+    }
+
+    #[test]
+    fn assigned_id() {
+        //主键由调用方赋值时不添加`AUTO_INCREMENT`
+        let meta = TableMeta {
+            auto_increment: false,
+            ..ORDER
+        };
+        assert_eq!(
+            meta.column_definition(&meta.columns[0]),
+            "`id` BIGINT NOT NULL"
+        );
+        //不是时间类型的`create_time`字段没有默认值
+        const COLUMNS: &[ColumnMeta] = &[column("create_time", "VARCHAR(255)", false)];
+        let meta = TableMeta {
+            columns: COLUMNS,
+            ..ORDER
+        };
+        assert_eq!(
+            meta.column_definition(&meta.columns[0]),
+            "`create_time` VARCHAR(255) NOT NULL"
+        );
+    }
+}
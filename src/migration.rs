@@ -0,0 +1,468 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use sqlx::{MySql, Pool};
+
+use crate::{registered_models, verify_table, DbColumn, Result, SchemaReport};
+
+/// 生成迁移文件的配置
+#[derive(Debug, Clone, Default)]
+pub struct MigrationOptions {
+    /// 是否删除数据库中多余的字段，默认只生成注释掉的`DROP COLUMN`语句
+    pub drop_extra_columns: bool,
+    /// 是否删除数据库中多余的索引，默认只生成注释掉的`DROP INDEX`语句
+    pub drop_extra_indexes: bool,
+}
+
+/// 一次可回滚的迁移，对应sqlx-cli `migrations/`目录中的
+/// `<version>_<description>.up.sql`和`<version>_<description>.down.sql`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Migration {
+    /// 版本号，UTC时间戳`%Y%m%d%H%M%S`
+    pub version: String,
+    /// 描述，会作为文件名的一部分
+    pub description: String,
+    /// 升级语句
+    pub up: String,
+    /// 回滚语句
+    pub down: String,
+}
+
+impl Migration {
+    /// 根据表结构比对结果生成迁移，表结构都一致时返回`None`
+    ///
+    /// 表不存在时生成`CREATE TABLE`，缺少的字段生成`ADD COLUMN`，类型或可空性不一致的字段生成`MODIFY COLUMN`，
+    /// 缺少的索引生成`CREATE INDEX`，不一致的索引先删除再创建，回滚语句按相反的顺序恢复数据库中原来的表结构
+    pub fn from_reports(
+        reports: &[SchemaReport],
+        description: &str,
+        options: &MigrationOptions,
+    ) -> Option<Self> {
+        let mut up = Vec::new();
+        let mut down = Vec::new();
+        for report in reports.iter().filter(|report| !report.is_ok()) {
+            let meta = &report.meta;
+            let table = meta.table;
+            if !report.table_exists {
+                up.push(format!("{};", meta.create_table_sql()));
+                down.push(format!("DROP TABLE `{}`;", table));
+                continue;
+            }
+            for column in &report.missing_columns {
+                up.push(format!(
+                    "ALTER TABLE `{}` ADD COLUMN {};",
+                    table,
+                    meta.column_definition(column)
+                ));
+                down.push(format!(
+                    "ALTER TABLE `{}` DROP COLUMN `{}`;",
+                    table, column.name
+                ));
+            }
+            for mismatch in &report.mismatched_columns {
+                up.push(format!(
+                    "ALTER TABLE `{}` MODIFY COLUMN {};",
+                    table,
+                    meta.column_definition(&mismatch.expected)
+                ));
+                down.push(format!(
+                    "ALTER TABLE `{}` MODIFY COLUMN {};",
+                    table,
+                    db_column_definition(&mismatch.actual)
+                ));
+            }
+            for column in &report.extra_columns {
+                let drop_sql = format!("ALTER TABLE `{}` DROP COLUMN `{}`;", table, column.name);
+                if options.drop_extra_columns {
+                    up.push(drop_sql);
+                    down.push(format!(
+                        "ALTER TABLE `{}` ADD COLUMN {};",
+                        table,
+                        db_column_definition(column)
+                    ));
+                } else {
+                    up.push(format!("-- {}", drop_sql));
+                }
+            }
+            //字段修改之后再修改索引，回滚时先恢复索引
+            for index in &report.missing_indexes {
+                up.push(format!("{};", index.create_index_sql(table)));
+                down.push(format!("{};", index.drop_index_sql(table)));
+            }
+            for mismatch in &report.mismatched_indexes {
+                up.push(format!("{};", mismatch.expected.drop_index_sql(table)));
+                up.push(format!("{};", mismatch.expected.create_index_sql(table)));
+                down.push(format!("{};", mismatch.actual.create_index_sql(table)));
+                down.push(format!("{};", mismatch.expected.drop_index_sql(table)));
+            }
+            for index in &report.extra_indexes {
+                let drop_sql = format!("DROP INDEX `{}` ON `{}`;", index.name, table);
+                if options.drop_extra_indexes {
+                    up.push(drop_sql);
+                    down.push(format!("{};", index.create_index_sql(table)));
+                } else {
+                    up.push(format!("-- {}", drop_sql));
+                }
+            }
+        }
+        if up.is_empty() {
+            return None;
+        }
+        down.reverse();
+        Some(Self {
+            version: chrono::Utc::now().format("%Y%m%d%H%M%S").to_string(),
+            description: normalize_description(description),
+            up: up.join("\n") + "\n",
+            down: down.join("\n") + "\n",
+        })
+    }
+
+    /// `<version>_<description>.up.sql`
+    pub fn up_file_name(&self) -> String {
+        format!("{}_{}.up.sql", self.version, self.description)
+    }
+
+    /// `<version>_<description>.down.sql`
+    pub fn down_file_name(&self) -> String {
+        format!("{}_{}.down.sql", self.version, self.description)
+    }
+
+    /// 把迁移写入目录，目录不存在时自动创建，返回升级和回滚文件的路径
+    pub fn write_to(&self, dir: impl AsRef<Path>) -> io::Result<(PathBuf, PathBuf)> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        let up_path = dir.join(self.up_file_name());
+        let down_path = dir.join(self.down_file_name());
+        fs::write(&up_path, &self.up)?;
+        fs::write(&down_path, &self.down)?;
+        Ok((up_path, down_path))
+    }
+}
+
+/// 比对所有已注册模型与数据库中的表结构并生成迁移，表结构都一致时返回`None`
+///
+/// # Examples
+///
+/// ```ignore
+/// sqlx_sqlhelper::register_model::<User>();
+/// if let Some(migration) = sqlx_sqlhelper::generate_migration(&*db::POOL, "add user nick").await? {
+///     migration.write_to("migrations")?;
+/// }
+/// ```
+pub async fn generate_migration(
+    pool: &Pool<MySql>,
+    description: &str,
+) -> Result<Option<Migration>> {
+    generate_migration_with(pool, description, &MigrationOptions::default()).await
+}
+
+/// 使用指定的配置生成迁移，参考[`generate_migration`]
+pub async fn generate_migration_with(
+    pool: &Pool<MySql>,
+    description: &str,
+    options: &MigrationOptions,
+) -> Result<Option<Migration>> {
+    let mut reports = Vec::new();
+    for meta in registered_models() {
+        reports.push(verify_table(pool, &meta).await?);
+    }
+    Ok(Migration::from_reports(&reports, description, options))
+}
+
+/// 数据库中字段的定义，用于回滚语句
+fn db_column_definition(column: &DbColumn) -> String {
+    let mut definition = format!(
+        "`{}` {} {}",
+        column.name,
+        column.column_type,
+        if column.nullable { "NULL" } else { "NOT NULL" }
+    );
+    if let Some(default) = &column.default {
+        definition.push_str(" DEFAULT ");
+        definition.push_str(&default_value_sql(default));
+    }
+    if !column.extra.is_empty() && !column.extra.eq_ignore_ascii_case("DEFAULT_GENERATED") {
+        definition.push(' ');
+        definition.push_str(&column.extra.replace("DEFAULT_GENERATED ", ""));
+    }
+    definition
+}
+
+/// `COLUMN_DEFAULT`中的默认值，MySQL返回的字符串默认值没有引号，MariaDB返回的带有引号
+fn default_value_sql(default: &str) -> String {
+    let is_raw = default.starts_with('\'')
+        || default.eq_ignore_ascii_case("NULL")
+        || default.to_uppercase().starts_with("CURRENT_TIMESTAMP")
+        || default.parse::<f64>().is_ok();
+    match is_raw {
+        true => default.to_string(),
+        false => format!("'{}'", default.replace('\'', "''")),
+    }
+}
+
+/// 描述转换为文件名，连续的非字母数字字符替换为一个`_`
+fn normalize_description(description: &str) -> String {
+    description
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColumnMeta, ColumnMismatch, DbIndex, IndexMeta, IndexMismatch, TableMeta};
+
+    const fn column(name: &'static str, sql_type_hint: &'static str, nullable: bool) -> ColumnMeta {
+        ColumnMeta {
+            name,
+            field: name,
+            rust_type: "",
+            sql_type_hint,
+            nullable,
+            is_id: false,
+        }
+    }
+
+    const ID: ColumnMeta = ColumnMeta {
+        is_id: true,
+        ..column("id", "BIGINT", false)
+    };
+    const ACCOUNT: ColumnMeta = column("account", "VARCHAR(255)", false);
+    const NICK: ColumnMeta = column("nick", "VARCHAR(255)", true);
+
+    const META: TableMeta = TableMeta {
+        table: "user",
+        struct_name: "User",
+        columns: &[ID, ACCOUNT, NICK],
+        id: "id",
+        auto_increment: true,
+        create_time: None,
+        update_time: None,
+        created_by: None,
+        updated_by: None,
+        version: None,
+        indexes: &[],
+    };
+
+    fn report() -> SchemaReport {
+        SchemaReport {
+            meta: META,
+            table_exists: true,
+            missing_columns: Vec::new(),
+            extra_columns: Vec::new(),
+            mismatched_columns: Vec::new(),
+            missing_indexes: Vec::new(),
+            extra_indexes: Vec::new(),
+            mismatched_indexes: Vec::new(),
+        }
+    }
+
+    fn db_column(name: &str, column_type: &str, nullable: bool, default: Option<&str>) -> DbColumn {
+        DbColumn {
+            name: name.to_string(),
+            column_type: column_type.to_string(),
+            nullable,
+            default: default.map(str::to_string),
+            extra: String::new(),
+        }
+    }
+
+    fn sync(reports: &[SchemaReport], options: &MigrationOptions) -> Migration {
+        Migration::from_reports(reports, "sync user", options).expect("migration")
+    }
+
+    #[test]
+    fn up_to_date() {
+        assert_eq!(
+            Migration::from_reports(&[report()], "noop", &MigrationOptions::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn missing_table() {
+        let report = SchemaReport {
+            table_exists: false,
+            ..report()
+        };
+        let migration = sync(&[report], &MigrationOptions::default());
+        assert_eq!(migration.up, format!("{};\n", META.create_table_sql()));
+        assert_eq!(migration.down, "DROP TABLE `user`;\n");
+    }
+
+    #[test]
+    fn missing_column() {
+        let report = SchemaReport {
+            missing_columns: vec![NICK],
+            ..report()
+        };
+        let migration = sync(&[report], &MigrationOptions::default());
+        assert_eq!(
+            migration.up,
+            "ALTER TABLE `user` ADD COLUMN `nick` VARCHAR(255) NULL;\n"
+        );
+        assert_eq!(migration.down, "ALTER TABLE `user` DROP COLUMN `nick`;\n");
+    }
+
+    #[test]
+    fn extra_column() {
+        let report = SchemaReport {
+            extra_columns: vec![db_column("legacy", "int(11)", false, Some("0"))],
+            ..report()
+        };
+        //默认只生成注释掉的`DROP COLUMN`，不需要回滚
+        let migration = sync(std::slice::from_ref(&report), &MigrationOptions::default());
+        assert_eq!(
+            migration.up,
+            "-- ALTER TABLE `user` DROP COLUMN `legacy`;\n"
+        );
+        assert_eq!(migration.down, "\n");
+
+        let options = MigrationOptions {
+            drop_extra_columns: true,
+            ..Default::default()
+        };
+        let migration = sync(&[report], &options);
+        assert_eq!(migration.up, "ALTER TABLE `user` DROP COLUMN `legacy`;\n");
+        assert_eq!(
+            migration.down,
+            "ALTER TABLE `user` ADD COLUMN `legacy` int(11) NOT NULL DEFAULT 0;\n"
+        );
+    }
+
+    #[test]
+    fn mismatched_column_round_trip() {
+        let report = SchemaReport {
+            mismatched_columns: vec![
+                ColumnMismatch {
+                    expected: ACCOUNT,
+                    actual: db_column("account", "varchar(64)", true, Some("guest")),
+                },
+                ColumnMismatch {
+                    expected: NICK,
+                    actual: db_column("nick", "int(11)", true, Some("'0'")),
+                },
+            ],
+            ..report()
+        };
+        let migration = sync(&[report], &MigrationOptions::default());
+        assert_eq!(
+            migration.up,
+            "ALTER TABLE `user` MODIFY COLUMN `account` VARCHAR(255) NOT NULL;\n\
+             ALTER TABLE `user` MODIFY COLUMN `nick` VARCHAR(255) NULL;\n"
+        );
+        //回滚语句按相反的顺序恢复原来的字段定义，MySQL返回的字符串默认值没有引号，MariaDB返回的带有引号
+        assert_eq!(
+            migration.down,
+            "ALTER TABLE `user` MODIFY COLUMN `nick` int(11) NULL DEFAULT '0';\n\
+             ALTER TABLE `user` MODIFY COLUMN `account` varchar(64) NULL DEFAULT 'guest';\n"
+        );
+    }
+
+    const UK_ACCOUNT: IndexMeta = IndexMeta {
+        name: "uk_user_account",
+        columns: &["account"],
+        unique: true,
+    };
+
+    fn db_index(name: &str, columns: &[&str], unique: bool) -> DbIndex {
+        DbIndex {
+            name: name.to_string(),
+            columns: columns.iter().map(|column| column.to_string()).collect(),
+            unique,
+        }
+    }
+
+    #[test]
+    fn missing_index() {
+        let report = SchemaReport {
+            missing_indexes: vec![UK_ACCOUNT],
+            ..report()
+        };
+        let migration = sync(&[report], &MigrationOptions::default());
+        assert_eq!(
+            migration.up,
+            "CREATE UNIQUE INDEX `uk_user_account` ON `user` (`account`);\n"
+        );
+        assert_eq!(migration.down, "DROP INDEX `uk_user_account` ON `user`;\n");
+    }
+
+    #[test]
+    fn mismatched_index_round_trip() {
+        let report = SchemaReport {
+            mismatched_indexes: vec![IndexMismatch {
+                expected: UK_ACCOUNT,
+                actual: db_index("uk_user_account", &["account", "nick"], false),
+            }],
+            ..report()
+        };
+        let migration = sync(&[report], &MigrationOptions::default());
+        assert_eq!(
+            migration.up,
+            "DROP INDEX `uk_user_account` ON `user`;\n\
+             CREATE UNIQUE INDEX `uk_user_account` ON `user` (`account`);\n"
+        );
+        assert_eq!(
+            migration.down,
+            "DROP INDEX `uk_user_account` ON `user`;\n\
+             CREATE INDEX `uk_user_account` ON `user` (`account`, `nick`);\n"
+        );
+    }
+
+    #[test]
+    fn extra_index() {
+        let report = SchemaReport {
+            extra_indexes: vec![db_index("idx_user_nick", &["nick"], false)],
+            ..report()
+        };
+        let migration = sync(std::slice::from_ref(&report), &MigrationOptions::default());
+        assert_eq!(migration.up, "-- DROP INDEX `idx_user_nick` ON `user`;\n");
+        assert_eq!(migration.down, "\n");
+
+        let options = MigrationOptions {
+            drop_extra_indexes: true,
+            ..Default::default()
+        };
+        let migration = sync(&[report], &options);
+        assert_eq!(migration.up, "DROP INDEX `idx_user_nick` ON `user`;\n");
+        assert_eq!(
+            migration.down,
+            "CREATE INDEX `idx_user_nick` ON `user` (`nick`);\n"
+        );
+    }
+
+    #[test]
+    fn file_names() {
+        let report = SchemaReport {
+            missing_columns: vec![NICK],
+            ..report()
+        };
+        let migration =
+            Migration::from_reports(&[report], "Add user.nick!", &MigrationOptions::default())
+                .expect("migration");
+        assert_eq!(migration.description, "add_user_nick");
+        assert_eq!(migration.version.len(), 14);
+        assert!(migration.version.chars().all(|c| c.is_ascii_digit()));
+        assert_eq!(
+            migration.up_file_name(),
+            format!("{}_add_user_nick.up.sql", migration.version)
+        );
+        assert_eq!(
+            migration.down_file_name(),
+            format!("{}_add_user_nick.down.sql", migration.version)
+        );
+
+        let dir =
+            std::env::temp_dir().join(format!("sqlx_sqlhelper_migration_{}", std::process::id()));
+        let (up_path, down_path) = migration.write_to(&dir).expect("write migration");
+        assert_eq!(up_path, dir.join(migration.up_file_name()));
+        assert_eq!(down_path, dir.join(migration.down_file_name()));
+        assert_eq!(fs::read_to_string(&up_path).unwrap(), migration.up);
+        assert_eq!(fs::read_to_string(&down_path).unwrap(), migration.down);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
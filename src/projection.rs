@@ -0,0 +1,100 @@
+use sqlx::{mysql::MySqlRow, FromRow};
+
+use crate::Crud;
+
+/// `SqlProjection`派生宏自动实现的trait，表示只查询模型部分字段的结构体
+///
+/// # Examples
+///
+/// ```ignore
+/// #[derive(sqlx::FromRow, SqlProjection)]
+/// #[projection(of = User)]
+/// pub struct UserSummary {
+///     pub id: i32,
+///     pub account: String,
+/// }
+///
+/// let users = User::list_as::<UserSummary>("AND age > ?", args).await?;
+/// ```
+///
+/// 投影结构体的字段必须是模型的表字段，在编译期检查：
+///
+/// ```no_run
+/// # mod db {
+/// #     use std::sync::LazyLock;
+/// #     use sqlx::{mysql::MySqlPoolOptions, MySql, Pool};
+/// #     pub static POOL: LazyLock<Pool<MySql>> = LazyLock::new(|| {
+/// #         MySqlPoolOptions::new().connect_lazy("mysql://localhost/test").unwrap()
+/// #     });
+/// # }
+/// use sqlx_sqlhelper::{SqlHelper, SqlProjection};
+///
+/// #[derive(sqlx::FromRow, SqlHelper)]
+/// pub struct User {
+///     #[id]
+///     pub id: i32,
+///     pub account: String,
+/// }
+///
+/// #[derive(sqlx::FromRow, SqlProjection)]
+/// #[projection(of = User)]
+/// pub struct UserSummary {
+///     pub account: String,
+/// }
+/// # fn main() {}
+/// ```
+///
+/// 字段不是模型的表字段时编译失败：
+///
+/// ```compile_fail
+/// # mod db {
+/// #     use std::sync::LazyLock;
+/// #     use sqlx::{mysql::MySqlPoolOptions, MySql, Pool};
+/// #     pub static POOL: LazyLock<Pool<MySql>> = LazyLock::new(|| {
+/// #         MySqlPoolOptions::new().connect_lazy("mysql://localhost/test").unwrap()
+/// #     });
+/// # }
+/// use sqlx_sqlhelper::{SqlHelper, SqlProjection};
+///
+/// #[derive(sqlx::FromRow, SqlHelper)]
+/// pub struct User {
+///     #[id]
+///     pub id: i32,
+///     pub account: String,
+/// }
+///
+/// #[derive(sqlx::FromRow, SqlProjection)]
+/// #[projection(of = User)]
+/// pub struct UserSummary {
+///     pub nickname: String,
+/// }
+/// # fn main() {}
+/// ```
+pub trait Projection: Sized + Send + Unpin + for<'r> FromRow<'r, MySqlRow> {
+    /// 投影的模型
+    type Of: Crud;
+
+    /// 表字段名和投影结构体字段名
+    const FIELDS: &'static [(&'static str, &'static str)];
+
+    /// `SELECT`之后的字段列表，表字段名和结构体字段名不同时使用`AS`
+    fn select_columns() -> String {
+        Self::FIELDS
+            .iter()
+            .map(|(column, field)| match column == field {
+                true => format!("`{}`", column),
+                false => format!("`{}` AS `{}`", column, field),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// 不带`WHERE`条件的查询语句
+    fn select_base_sql() -> String {
+        format!(
+            "SELECT {} FROM `{}`",
+            Self::select_columns(),
+            <Self::Of as Crud>::TABLE
+        )
+    }
+}
@@ -0,0 +1,341 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use sqlx::{mysql::MySqlRow, Arguments, Decode, Encode, MySql, Pool, Row, Type};
+
+use crate::{Crud, Error, Result};
+
+/// 每次`IN (...)`查询最多绑定的参数数量
+const IN_CHUNK_SIZE: usize = 1000;
+
+/// 批量查询时外键字段的别名
+const FOREIGN_KEY_ALIAS: &str = "__sqlhelper_fk";
+
+/// `SELECT`语句中的字段列表
+fn select_columns<T: Crud>() -> String {
+    T::COLUMNS
+        .iter()
+        .map(|column| format!("`{}`", column))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// 查询`foreign_key`字段等于`key`的所有数据，按主键排序，`has_many`生成的方法会调用此函数
+pub async fn list_by_foreign_key<T, K>(
+    pool: &Pool<MySql>,
+    foreign_key: &str,
+    key: K,
+) -> Result<Vec<T>>
+where
+    T: Crud,
+    K: for<'q> Encode<'q, MySql> + Type<MySql> + Send,
+{
+    let sql = format!(
+        "SELECT {} FROM `{}` WHERE `{}` = ? ORDER BY `{}`",
+        select_columns::<T>(),
+        T::TABLE,
+        foreign_key,
+        T::META.id
+    );
+    Ok(sqlx::query_as::<_, T>(&sql)
+        .bind(key)
+        .fetch_all(pool)
+        .await?)
+}
+
+/// 查询`foreign_key`字段等于`key`的主键最小的一条数据，`has_one`生成的方法会调用此函数
+pub async fn get_by_foreign_key<T, K>(
+    pool: &Pool<MySql>,
+    foreign_key: &str,
+    key: K,
+) -> Result<Option<T>>
+where
+    T: Crud,
+    K: for<'q> Encode<'q, MySql> + Type<MySql> + Send,
+{
+    let sql = format!(
+        "SELECT {} FROM `{}` WHERE `{}` = ? ORDER BY `{}` LIMIT 1",
+        select_columns::<T>(),
+        T::TABLE,
+        foreign_key,
+        T::META.id
+    );
+    Ok(sqlx::query_as::<_, T>(&sql)
+        .bind(key)
+        .fetch_optional(pool)
+        .await?)
+}
+
+/// 通过`foreign_key IN (...)`批量查询数据，并按照外键分组
+///
+/// 外键重复时只查询一次，超过1000个外键时分批查询，每个外键的数据按主键排序。`with_orders`等预加载方法会调用此函数。
+pub async fn load_by_foreign_key<T, K>(
+    pool: &Pool<MySql>,
+    foreign_key: &str,
+    keys: &[K],
+) -> Result<HashMap<K, Vec<T>>>
+where
+    T: Crud,
+    K: for<'q> Encode<'q, MySql>
+        + for<'r> Decode<'r, MySql>
+        + Type<MySql>
+        + Hash
+        + Eq
+        + Clone
+        + Send,
+{
+    let keys = keys.iter().cloned().collect::<HashSet<_>>();
+    let keys = keys.into_iter().collect::<Vec<_>>();
+    let mut map: HashMap<K, Vec<T>> = HashMap::new();
+    for chunk in keys.chunks(IN_CHUNK_SIZE) {
+        let sql = format!(
+            "SELECT {}, `{}` AS `{}` FROM `{}` WHERE `{}` IN ({}) ORDER BY `{}`",
+            select_columns::<T>(),
+            foreign_key,
+            FOREIGN_KEY_ALIAS,
+            T::TABLE,
+            foreign_key,
+            vec!["?"; chunk.len()].join(", "),
+            T::META.id
+        );
+        let mut args = sqlx::mysql::MySqlArguments::default();
+        for key in chunk {
+            args.add(key.clone())
+                .map_err(|e| Error::Bind(e.to_string()))?;
+        }
+        let rows: Vec<MySqlRow> = sqlx::query_with(&sql, args).fetch_all(pool).await?;
+        for row in rows {
+            let key: K = row.try_get(FOREIGN_KEY_ALIAS)?;
+            map.entry(key).or_default().push(T::from_row(&row)?);
+        }
+    }
+    Ok(map)
+}
+
+/// 和`load_by_foreign_key`相同，每个外键只保留主键最小的一条数据，与`get_by_foreign_key`的结果一致
+///
+/// `with_profile`等`has_one`的预加载方法会调用此函数。
+pub async fn load_one_by_foreign_key<T, K>(
+    pool: &Pool<MySql>,
+    foreign_key: &str,
+    keys: &[K],
+) -> Result<HashMap<K, T>>
+where
+    T: Crud,
+    K: for<'q> Encode<'q, MySql>
+        + for<'r> Decode<'r, MySql>
+        + Type<MySql>
+        + Hash
+        + Eq
+        + Clone
+        + Send,
+{
+    Ok(load_by_foreign_key::<T, K>(pool, foreign_key, keys)
+        .await?
+        .into_iter()
+        .filter_map(|(key, children)| children.into_iter().next().map(|child| (key, child)))
+        .collect())
+}
+
+/// 通过主键`IN (...)`批量查询数据，返回以主键为key的`HashMap`
+///
+/// 主键重复时只查询一次，超过1000个主键时分批查询。`#[belongs_to]`生成的`load_users`等方法会调用此函数。
+pub async fn load_by_ids<T>(pool: &Pool<MySql>, ids: &[T::Id]) -> Result<HashMap<T::Id, T>>
+where
+    T: Crud,
+    T::Id: for<'q> Encode<'q, MySql> + Type<MySql> + Hash + Eq,
+{
+    let ids = ids.iter().cloned().collect::<HashSet<_>>();
+    let ids = ids.into_iter().collect::<Vec<_>>();
+    let mut map = HashMap::with_capacity(ids.len());
+    for chunk in ids.chunks(IN_CHUNK_SIZE) {
+        let sql = format!(
+            "SELECT {} FROM `{}` WHERE `{}` IN ({})",
+            select_columns::<T>(),
+            T::TABLE,
+            T::META.id,
+            vec!["?"; chunk.len()].join(", ")
+        );
+        let mut args = sqlx::mysql::MySqlArguments::default();
+        for id in chunk {
+            args.add(id.clone())
+                .map_err(|e| Error::Bind(e.to_string()))?;
+        }
+        let rows = sqlx::query_as_with::<_, T, _>(&sql, args)
+            .fetch_all(pool)
+            .await?;
+        map.extend(rows.into_iter().map(|row| (row.id(), row)));
+    }
+    Ok(map)
+}
+
+/// `sync_roles`等多对多同步方法的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncChanges<R> {
+    /// 新增的关联
+    pub attached: Vec<R>,
+    /// 删除的关联
+    pub detached: Vec<R>,
+}
+
+/// 通过中间表查询关联的数据，`many_to_many`生成的`roles`等方法会调用此函数
+pub async fn list_through<T, K>(
+    pool: &Pool<MySql>,
+    through: &str,
+    foreign_key: &str,
+    related_key: &str,
+    key: K,
+) -> Result<Vec<T>>
+where
+    T: Crud,
+    K: for<'q> Encode<'q, MySql> + Type<MySql> + Send,
+{
+    let sql = format!(
+        "SELECT {} FROM `{}` WHERE `{}` IN (SELECT `{}` FROM `{}` WHERE `{}` = ?)",
+        select_columns::<T>(),
+        T::TABLE,
+        T::META.id,
+        related_key,
+        through,
+        foreign_key
+    );
+    Ok(sqlx::query_as::<_, T>(&sql)
+        .bind(key)
+        .fetch_all(pool)
+        .await?)
+}
+
+/// 在中间表中添加关联，关联已经存在时返回`false`
+pub async fn attach_through<K, R>(
+    pool: &Pool<MySql>,
+    through: &str,
+    foreign_key: &str,
+    related_key: &str,
+    key: K,
+    related: R,
+) -> Result<bool>
+where
+    K: for<'q> Encode<'q, MySql> + Type<MySql> + Clone + Send,
+    R: for<'q> Encode<'q, MySql> + Type<MySql> + Clone + Send,
+{
+    let sql = format!(
+        "INSERT INTO `{through}` (`{fk}`, `{rk}`) SELECT ?, ? FROM DUAL \
+         WHERE NOT EXISTS (SELECT 1 FROM `{through}` WHERE `{fk}` = ? AND `{rk}` = ?)",
+        through = through,
+        fk = foreign_key,
+        rk = related_key
+    );
+    let rows_affected = sqlx::query(&sql)
+        .bind(key.clone())
+        .bind(related.clone())
+        .bind(key)
+        .bind(related)
+        .execute(pool)
+        .await?
+        .rows_affected();
+    Ok(rows_affected > 0)
+}
+
+/// 删除中间表中的关联，关联不存在时返回`false`
+pub async fn detach_through<K, R>(
+    pool: &Pool<MySql>,
+    through: &str,
+    foreign_key: &str,
+    related_key: &str,
+    key: K,
+    related: R,
+) -> Result<bool>
+where
+    K: for<'q> Encode<'q, MySql> + Type<MySql> + Send,
+    R: for<'q> Encode<'q, MySql> + Type<MySql> + Send,
+{
+    let sql = format!(
+        "DELETE FROM `{}` WHERE `{}` = ? AND `{}` = ?",
+        through, foreign_key, related_key
+    );
+    let rows_affected = sqlx::query(&sql)
+        .bind(key)
+        .bind(related)
+        .execute(pool)
+        .await?
+        .rows_affected();
+    Ok(rows_affected > 0)
+}
+
+/// 把中间表中`key`的关联同步为`related`
+///
+/// 在一个事务中通过`SELECT ... FOR UPDATE`读取已有的关联，只删除多余的关联并插入缺少的关联，
+/// 不会先全部删除再插入
+pub async fn sync_through<K, R>(
+    pool: &Pool<MySql>,
+    through: &str,
+    foreign_key: &str,
+    related_key: &str,
+    key: K,
+    related: &[R],
+) -> Result<SyncChanges<R>>
+where
+    K: for<'q> Encode<'q, MySql> + Type<MySql> + Clone + Send,
+    R: for<'q> Encode<'q, MySql> + for<'r> Decode<'r, MySql> + Type<MySql>,
+    R: Hash + Eq + Clone + Send + Unpin,
+{
+    let mut tran = pool.begin().await?;
+    let select_sql = format!(
+        "SELECT `{}` FROM `{}` WHERE `{}` = ? FOR UPDATE",
+        related_key, through, foreign_key
+    );
+    let current = sqlx::query_scalar::<_, R>(&select_sql)
+        .bind(key.clone())
+        .fetch_all(&mut *tran)
+        .await?;
+    let current_set = current.iter().collect::<HashSet<_>>();
+    let related_set = related.iter().collect::<HashSet<_>>();
+    let detached = current
+        .iter()
+        .filter(|r| !related_set.contains(r))
+        .cloned()
+        .collect::<Vec<_>>();
+    let mut seen = HashSet::new();
+    let attached = related
+        .iter()
+        .filter(|r| !current_set.contains(r) && seen.insert(*r))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    for chunk in detached.chunks(IN_CHUNK_SIZE) {
+        let sql = format!(
+            "DELETE FROM `{}` WHERE `{}` = ? AND `{}` IN ({})",
+            through,
+            foreign_key,
+            related_key,
+            vec!["?"; chunk.len()].join(", ")
+        );
+        let mut args = sqlx::mysql::MySqlArguments::default();
+        args.add(key.clone())
+            .map_err(|e| Error::Bind(e.to_string()))?;
+        for r in chunk {
+            args.add(r.clone())
+                .map_err(|e| Error::Bind(e.to_string()))?;
+        }
+        sqlx::query_with(&sql, args).execute(&mut *tran).await?;
+    }
+    for chunk in attached.chunks(IN_CHUNK_SIZE) {
+        let sql = format!(
+            "INSERT INTO `{}` (`{}`, `{}`) VALUES {}",
+            through,
+            foreign_key,
+            related_key,
+            vec!["(?, ?)"; chunk.len()].join(", ")
+        );
+        let mut args = sqlx::mysql::MySqlArguments::default();
+        for r in chunk {
+            args.add(key.clone())
+                .map_err(|e| Error::Bind(e.to_string()))?;
+            args.add(r.clone())
+                .map_err(|e| Error::Bind(e.to_string()))?;
+        }
+        sqlx::query_with(&sql, args).execute(&mut *tran).await?;
+    }
+    tran.commit().await?;
+    Ok(SyncChanges { attached, detached })
+}
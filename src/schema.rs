@@ -0,0 +1,450 @@
+use std::fmt::{self, Display};
+use std::sync::Mutex;
+
+use sqlx::{mysql::MySqlRow, FromRow, MySql, Pool, Row};
+
+use crate::{ColumnMeta, Crud, IndexMeta, Result, TableMeta};
+
+/// 数据库中实际的表字段，来自`information_schema.COLUMNS`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DbColumn {
+    /// 字段名
+    pub name: String,
+    /// 字段类型，例如`varchar(255)`、`int unsigned`
+    pub column_type: String,
+    /// 是否可以为`NULL`
+    pub nullable: bool,
+    /// 默认值
+    pub default: Option<String>,
+    /// 额外信息，例如`auto_increment`
+    pub extra: String,
+}
+
+impl<'r> FromRow<'r, MySqlRow> for DbColumn {
+    fn from_row(row: &'r MySqlRow) -> Result<Self, sqlx::Error> {
+        let is_nullable: String = row.try_get("is_nullable")?;
+        Ok(Self {
+            name: row.try_get("name")?,
+            column_type: row.try_get("column_type")?,
+            nullable: is_nullable == "YES",
+            default: row.try_get("column_default")?,
+            extra: row.try_get("extra")?,
+        })
+    }
+}
+
+/// 数据库中实际的索引，来自`information_schema.STATISTICS`，不包含主键
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DbIndex {
+    /// 索引名
+    pub name: String,
+    /// 索引包含的字段，按照索引中的顺序
+    pub columns: Vec<String>,
+    /// 是否为唯一索引
+    pub unique: bool,
+}
+
+impl DbIndex {
+    /// 是否与模型中声明的索引一致
+    pub fn matches(&self, index: &IndexMeta) -> bool {
+        self.unique == index.unique && self.columns.iter().eq(index.columns.iter())
+    }
+
+    /// 在已有的表上创建该索引的语句，用于回滚语句
+    pub fn create_index_sql(&self, table: &str) -> String {
+        format!(
+            "CREATE {}INDEX `{}` ON `{}` ({})",
+            if self.unique { "UNIQUE " } else { "" },
+            self.name,
+            table,
+            self.columns
+                .iter()
+                .map(|column| format!("`{}`", column))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+/// 模型中声明的索引与数据库中同名索引的字段或唯一性不一致
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexMismatch {
+    /// 模型中的索引
+    pub expected: IndexMeta,
+    /// 数据库中的索引
+    pub actual: DbIndex,
+}
+
+/// 模型字段与数据库字段类型或者可空性不一致
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnMismatch {
+    /// 模型中的字段
+    pub expected: ColumnMeta,
+    /// 数据库中的字段
+    pub actual: DbColumn,
+}
+
+impl ColumnMismatch {
+    /// 字段类型是否不兼容
+    pub fn type_mismatch(&self) -> bool {
+        !is_type_compatible(self.expected.sql_type_hint, &self.actual.column_type)
+    }
+
+    /// 可空性是否不一致
+    pub fn nullable_mismatch(&self) -> bool {
+        self.expected.nullable != self.actual.nullable
+    }
+}
+
+/// 模型与数据库表结构的比对结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaReport {
+    /// 模型的表元数据
+    pub meta: TableMeta,
+    /// 数据库中是否存在该表
+    pub table_exists: bool,
+    /// 模型中有但是数据库中没有的字段
+    pub missing_columns: Vec<ColumnMeta>,
+    /// 数据库中有但是模型中没有的字段
+    pub extra_columns: Vec<DbColumn>,
+    /// 类型或者可空性不一致的字段
+    pub mismatched_columns: Vec<ColumnMismatch>,
+    /// 模型中声明但是数据库中没有的索引
+    pub missing_indexes: Vec<IndexMeta>,
+    /// 数据库中有但是模型中没有声明的索引
+    pub extra_indexes: Vec<DbIndex>,
+    /// 字段或唯一性不一致的同名索引
+    pub mismatched_indexes: Vec<IndexMismatch>,
+}
+
+impl SchemaReport {
+    /// 表结构是否与模型一致
+    pub fn is_ok(&self) -> bool {
+        self.table_exists
+            && self.missing_columns.is_empty()
+            && self.extra_columns.is_empty()
+            && self.mismatched_columns.is_empty()
+            && self.missing_indexes.is_empty()
+            && self.extra_indexes.is_empty()
+            && self.mismatched_indexes.is_empty()
+    }
+}
+
+impl Display for SchemaReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.table_exists {
+            return write!(f, "table `{}` does not exist", self.meta.table);
+        }
+        if self.is_ok() {
+            return write!(f, "table `{}` is up to date", self.meta.table);
+        }
+        let null_sql = |nullable| if nullable { "NULL" } else { "NOT NULL" };
+        write!(f, "table `{}` drifted:", self.meta.table)?;
+        for column in &self.missing_columns {
+            write!(f, "\n  missing column `{}`", column.name)?;
+        }
+        for column in &self.extra_columns {
+            write!(f, "\n  extra column `{}`", column.name)?;
+        }
+        for mismatch in &self.mismatched_columns {
+            write!(
+                f,
+                "\n  column `{}` expected {} {}, found {} {}",
+                mismatch.expected.name,
+                mismatch.expected.sql_type_hint,
+                null_sql(mismatch.expected.nullable),
+                mismatch.actual.column_type,
+                null_sql(mismatch.actual.nullable),
+            )?;
+        }
+        for index in &self.missing_indexes {
+            write!(f, "\n  missing index `{}`", index.name)?;
+        }
+        for index in &self.extra_indexes {
+            write!(f, "\n  extra index `{}`", index.name)?;
+        }
+        for mismatch in &self.mismatched_indexes {
+            write!(f, "\n  index `{}` differs", mismatch.expected.name)?;
+        }
+        Ok(())
+    }
+}
+
+/// 比对模型与数据库中的表结构
+///
+/// 根据`information_schema.COLUMNS`检查缺少、多余以及类型、可空性不一致的字段，
+/// 根据`information_schema.STATISTICS`检查`#[index]`、`#[unique]`声明的索引。
+/// 字段类型只比较类型种类，例如`VARCHAR(255)`与`varchar(64)`、`text`视为一致。
+///
+/// # Examples
+///
+/// ```ignore
+/// let report = sqlx_sqlhelper::verify_schema::<User>(&*db::POOL).await?;
+/// if !report.is_ok() {
+///     panic!("{}", report);
+/// }
+/// ```
+pub async fn verify_schema<T: Crud>(pool: &Pool<MySql>) -> Result<SchemaReport> {
+    verify_table(pool, &T::META).await
+}
+
+/// 根据表元数据比对数据库中的表结构，参考[`verify_schema`]
+pub async fn verify_table(pool: &Pool<MySql>, meta: &TableMeta) -> Result<SchemaReport> {
+    let db_columns = fetch_columns(pool, meta.table).await?;
+    let mut report = SchemaReport {
+        meta: *meta,
+        table_exists: !db_columns.is_empty(),
+        missing_columns: Vec::new(),
+        extra_columns: Vec::new(),
+        mismatched_columns: Vec::new(),
+        missing_indexes: Vec::new(),
+        extra_indexes: Vec::new(),
+        mismatched_indexes: Vec::new(),
+    };
+    for column in meta.columns {
+        match db_columns
+            .iter()
+            .find(|db_column| db_column.name == column.name)
+        {
+            Some(db_column) => {
+                let mismatch = ColumnMismatch {
+                    expected: *column,
+                    actual: db_column.clone(),
+                };
+                if mismatch.type_mismatch() || mismatch.nullable_mismatch() {
+                    report.mismatched_columns.push(mismatch);
+                }
+            }
+            None => report.missing_columns.push(*column),
+        }
+    }
+    report.extra_columns = db_columns
+        .into_iter()
+        .filter(|db_column| meta.column(&db_column.name).is_none())
+        .collect();
+    if report.table_exists {
+        compare_indexes(&mut report, fetch_indexes(pool, meta.table).await?);
+    }
+    Ok(report)
+}
+
+/// 按照索引名比对模型中声明的索引与数据库中的索引
+fn compare_indexes(report: &mut SchemaReport, db_indexes: Vec<DbIndex>) {
+    for index in report.meta.indexes {
+        match db_indexes
+            .iter()
+            .find(|db_index| db_index.name == index.name)
+        {
+            Some(db_index) if db_index.matches(index) => {}
+            Some(db_index) => report.mismatched_indexes.push(IndexMismatch {
+                expected: *index,
+                actual: db_index.clone(),
+            }),
+            None => report.missing_indexes.push(*index),
+        }
+    }
+    report.extra_indexes = db_indexes
+        .into_iter()
+        .filter(|db_index| {
+            !report
+                .meta
+                .indexes
+                .iter()
+                .any(|index| index.name == db_index.name)
+        })
+        .collect();
+}
+
+/// 查询当前数据库中表的所有索引，不包含主键
+pub async fn fetch_indexes(pool: &Pool<MySql>, table: &str) -> Result<Vec<DbIndex>> {
+    let rows = sqlx::query_as::<_, (String, String, i64)>(
+        "SELECT CAST(INDEX_NAME AS CHAR), CAST(COLUMN_NAME AS CHAR), CAST(NON_UNIQUE AS SIGNED) \
+         FROM information_schema.STATISTICS \
+         WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ? AND INDEX_NAME <> 'PRIMARY' \
+         ORDER BY INDEX_NAME, SEQ_IN_INDEX",
+    )
+    .bind(table)
+    .fetch_all(pool)
+    .await?;
+    let mut indexes: Vec<DbIndex> = Vec::new();
+    for (name, column, non_unique) in rows {
+        match indexes.last_mut() {
+            Some(index) if index.name == name => index.columns.push(column),
+            _ => indexes.push(DbIndex {
+                name,
+                columns: vec![column],
+                unique: non_unique == 0,
+            }),
+        }
+    }
+    Ok(indexes)
+}
+
+/// 查询当前数据库中表的所有字段，表不存在时返回空数组
+pub async fn fetch_columns(pool: &Pool<MySql>, table: &str) -> Result<Vec<DbColumn>> {
+    Ok(sqlx::query_as::<_, DbColumn>(
+        "SELECT CAST(COLUMN_NAME AS CHAR) AS name, CAST(COLUMN_TYPE AS CHAR) AS column_type, \
+         CAST(IS_NULLABLE AS CHAR) AS is_nullable, CAST(COLUMN_DEFAULT AS CHAR) AS column_default, \
+         CAST(EXTRA AS CHAR) AS extra \
+         FROM information_schema.COLUMNS \
+         WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ? ORDER BY ORDINAL_POSITION",
+    )
+    .bind(table)
+    .fetch_all(pool)
+    .await?)
+}
+
+/// 判断模型字段类型与数据库字段类型是否属于同一种类
+fn is_type_compatible(expected: &str, actual: &str) -> bool {
+    let (expected_kind, expected_unsigned) = type_kind(expected);
+    let (actual_kind, actual_unsigned) = type_kind(actual);
+    expected_kind == actual_kind && expected_unsigned == actual_unsigned
+}
+
+/// 类型种类以及是否无符号，`tinyint(1)`视为`bool`，字符串类型统一视为`text`
+fn type_kind(column_type: &str) -> (String, bool) {
+    let column_type = column_type.trim().to_lowercase();
+    let unsigned = column_type.contains("unsigned");
+    if column_type.starts_with("tinyint(1)") || column_type == "bool" || column_type == "boolean" {
+        return ("bool".to_string(), false);
+    }
+    let name = column_type
+        .split(|c: char| c == '(' || c.is_whitespace())
+        .next()
+        .unwrap_or_default();
+    let kind = match name {
+        "char" | "varchar" | "tinytext" | "text" | "mediumtext" | "longtext" | "enum" | "set" => {
+            "text"
+        }
+        "binary" | "varbinary" | "tinyblob" | "blob" | "mediumblob" | "longblob" => "blob",
+        "integer" => "int",
+        "numeric" => "decimal",
+        name => name,
+    };
+    (kind.to_string(), unsigned)
+}
+
+static REGISTRY: Mutex<Vec<TableMeta>> = Mutex::new(Vec::new());
+
+/// 注册模型，注册后可以通过[`verify_registered_schemas`]统一比对表结构
+///
+/// 同一张表重复注册时只保留一次，返回是否为新注册的模型
+///
+/// # Examples
+///
+/// ```ignore
+/// sqlx_sqlhelper::register_model::<User>();
+/// sqlx_sqlhelper::register_model::<Order>();
+/// for report in sqlx_sqlhelper::verify_registered_schemas(&*db::POOL).await? {
+///     assert!(report.is_ok(), "{}", report);
+/// }
+/// ```
+pub fn register_model<T: Crud>() -> bool {
+    let mut registry = REGISTRY.lock().unwrap_or_else(|e| e.into_inner());
+    if registry.iter().any(|meta| meta.table == T::META.table) {
+        return false;
+    }
+    registry.push(T::META);
+    true
+}
+
+/// 所有已注册模型的表元数据
+pub fn registered_models() -> Vec<TableMeta> {
+    REGISTRY.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// 比对所有已注册模型的表结构
+pub async fn verify_registered_schemas(pool: &Pool<MySql>) -> Result<Vec<SchemaReport>> {
+    let mut reports = Vec::new();
+    for meta in registered_models() {
+        reports.push(verify_table(pool, &meta).await?);
+    }
+    Ok(reports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn type_compatible() {
+        assert!(is_type_compatible("VARCHAR(255)", "varchar(64)"));
+        assert!(is_type_compatible("VARCHAR(255)", "text"));
+        assert!(is_type_compatible("TINYINT(1)", "tinyint(1)"));
+        assert!(is_type_compatible("BIGINT UNSIGNED", "bigint(20) unsigned"));
+        assert!(!is_type_compatible("BIGINT UNSIGNED", "bigint(20)"));
+        assert!(!is_type_compatible("INT", "bigint"));
+    }
+
+    #[test]
+    fn uuid_binary() {
+        //`Uuid`映射为`BINARY(16)`
+        assert!(is_type_compatible("BINARY(16)", "binary(16)"));
+        assert!(!is_type_compatible("BINARY(16)", "char(36)"));
+    }
+
+    #[test]
+    fn compare_indexes_by_name() {
+        const META: TableMeta = TableMeta {
+            table: "user",
+            struct_name: "User",
+            columns: &[],
+            id: "id",
+            auto_increment: true,
+            create_time: None,
+            update_time: None,
+            created_by: None,
+            updated_by: None,
+            version: None,
+            indexes: &[
+                IndexMeta {
+                    name: "uk_user_email",
+                    columns: &["email"],
+                    unique: true,
+                },
+                IndexMeta {
+                    name: "idx_user_org_id_nick",
+                    columns: &["org_id", "nick"],
+                    unique: false,
+                },
+                IndexMeta {
+                    name: "idx_user_phone",
+                    columns: &["phone"],
+                    unique: false,
+                },
+            ],
+        };
+        let db_index = |name: &str, columns: &[&str], unique: bool| DbIndex {
+            name: name.to_string(),
+            columns: columns.iter().map(|column| column.to_string()).collect(),
+            unique,
+        };
+        let mut report = SchemaReport {
+            meta: META,
+            table_exists: true,
+            missing_columns: Vec::new(),
+            extra_columns: Vec::new(),
+            mismatched_columns: Vec::new(),
+            missing_indexes: Vec::new(),
+            extra_indexes: Vec::new(),
+            mismatched_indexes: Vec::new(),
+        };
+        compare_indexes(
+            &mut report,
+            vec![
+                db_index("uk_user_email", &["email"], true),
+                //字段顺序不同也视为不一致
+                db_index("idx_user_org_id_nick", &["nick", "org_id"], false),
+                db_index("idx_legacy", &["legacy"], false),
+            ],
+        );
+        assert_eq!(report.missing_indexes, vec![META.indexes[2]]);
+        assert_eq!(
+            report.extra_indexes,
+            vec![db_index("idx_legacy", &["legacy"], false)]
+        );
+        assert_eq!(report.mismatched_indexes.len(), 1);
+        assert_eq!(report.mismatched_indexes[0].expected, META.indexes[1]);
+        assert!(!report.is_ok());
+    }
+}
@@ -0,0 +1,198 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+use sqlx::{Acquire, MySql, Pool, Transaction};
+
+use crate::Result;
+
+/// MySQL死锁的错误码：`ER_LOCK_DEADLOCK`
+const ER_LOCK_DEADLOCK: u16 = 1213;
+/// MySQL锁等待超时的错误码：`ER_LOCK_WAIT_TIMEOUT`
+const ER_LOCK_WAIT_TIMEOUT: u16 = 1205;
+
+/// `transaction`闭包返回的`Future`
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// `transaction_with`的重试配置
+#[derive(Debug, Clone)]
+pub struct TransactionOptions {
+    /// 遇到死锁、锁等待超时时的最大重试次数
+    pub max_retries: u32,
+    /// 第一次重试前的等待时间，之后每次翻倍
+    pub backoff: Duration,
+    /// 最大等待时间
+    pub max_backoff: Duration,
+}
+
+impl Default for TransactionOptions {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            backoff: Duration::from_millis(50),
+            max_backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+impl TransactionOptions {
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.backoff
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_backoff)
+    }
+
+    /// 第`attempt`次执行失败后是否重试，只重试死锁和锁等待超时
+    fn should_retry(&self, attempt: u32, error_number: Option<u16>) -> bool {
+        attempt < self.max_retries && error_number.is_some_and(is_retryable_number)
+    }
+}
+
+/// 在事务中执行闭包，返回`Ok`时提交，返回`Err`时回滚
+///
+/// 闭包panic时事务会在`Transaction`被drop时回滚。遇到MySQL死锁（1213）、
+/// 锁等待超时（1205）时，使用`TransactionOptions::default()`的配置重新执行闭包。
+///
+/// # Examples
+///
+/// ```ignore
+/// let user = sqlx_sqlhelper::transaction(&*db::POOL, |tran| {
+///     Box::pin(async move {
+///         let mut user = User::tran_get_by_id_for_update(tran, 1).await?;
+///         user.tran_update(tran).await?;
+///         Ok(user)
+///     })
+/// })
+/// .await?;
+/// ```
+pub async fn transaction<T, F>(pool: &Pool<MySql>, f: F) -> Result<T>
+where
+    F: for<'c> FnMut(&'c mut Transaction<'static, MySql>) -> BoxFuture<'c, Result<T>>,
+{
+    transaction_with(pool, TransactionOptions::default(), f).await
+}
+
+/// 和`transaction`相同，可以指定重试配置
+pub async fn transaction_with<T, F>(
+    pool: &Pool<MySql>,
+    options: TransactionOptions,
+    mut f: F,
+) -> Result<T>
+where
+    F: for<'c> FnMut(&'c mut Transaction<'static, MySql>) -> BoxFuture<'c, Result<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        let result = run_transaction(pool, &mut f).await;
+        match result {
+            Err(e) if options.should_retry(attempt, e.mysql_error_number()) => {
+                attempt += 1;
+                tokio::time::sleep(options.backoff(attempt)).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+async fn run_transaction<T, F>(pool: &Pool<MySql>, f: &mut F) -> Result<T>
+where
+    F: for<'c> FnMut(&'c mut Transaction<'static, MySql>) -> BoxFuture<'c, Result<T>>,
+{
+    let mut tran = pool.begin().await?;
+    match f(&mut tran).await {
+        Ok(value) => {
+            tran.commit().await?;
+            Ok(value)
+        }
+        Err(e) => {
+            //回滚失败时连接会被丢弃，返回闭包的错误
+            let _ = tran.rollback().await;
+            Err(e)
+        }
+    }
+}
+
+/// 在已有事务中通过`SAVEPOINT`执行闭包，返回`Ok`时释放保存点，返回`Err`时回滚到保存点
+///
+/// 可以在`transaction`的闭包或者其他`tran_*`方法中组合使用。
+///
+/// # Examples
+///
+/// ```ignore
+/// sqlx_sqlhelper::transaction(&*db::POOL, |tran| {
+///     Box::pin(async move {
+///         order.tran_insert(tran).await?;
+///         //记录日志失败时不影响订单
+///         let _ = sqlx_sqlhelper::savepoint(tran, |tran| {
+///             Box::pin(async move { log.tran_insert(tran).await })
+///         })
+///         .await;
+///         Ok(())
+///     })
+/// })
+/// .await?;
+/// ```
+pub async fn savepoint<'t, T, F>(tran: &'t mut Transaction<'_, MySql>, f: F) -> Result<T>
+where
+    F: for<'c> FnOnce(&'c mut Transaction<'t, MySql>) -> BoxFuture<'c, Result<T>>,
+{
+    let mut savepoint = tran.begin().await?;
+    match f(&mut savepoint).await {
+        Ok(value) => {
+            savepoint.commit().await?;
+            Ok(value)
+        }
+        Err(e) => {
+            let _ = savepoint.rollback().await;
+            Err(e)
+        }
+    }
+}
+
+fn is_retryable_number(number: u16) -> bool {
+    matches!(number, ER_LOCK_DEADLOCK | ER_LOCK_WAIT_TIMEOUT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_until_max() {
+        let options = TransactionOptions::default();
+        let backoffs = (1..=6)
+            .map(|attempt| options.backoff(attempt).as_millis())
+            .collect::<Vec<_>>();
+        assert_eq!(backoffs, vec![50, 100, 200, 400, 800, 1000]);
+        //重试次数很大时不会溢出
+        assert_eq!(options.backoff(u32::MAX), options.max_backoff);
+    }
+
+    #[test]
+    fn retry_until_max_retries() {
+        let options = TransactionOptions {
+            max_retries: 2,
+            ..Default::default()
+        };
+        assert!(options.should_retry(0, Some(ER_LOCK_DEADLOCK)));
+        assert!(options.should_retry(1, Some(ER_LOCK_WAIT_TIMEOUT)));
+        assert!(!options.should_retry(2, Some(ER_LOCK_DEADLOCK)));
+
+        let options = TransactionOptions {
+            max_retries: 0,
+            ..Default::default()
+        };
+        assert!(!options.should_retry(0, Some(ER_LOCK_DEADLOCK)));
+    }
+
+    #[test]
+    fn retry_only_lock_errors() {
+        let options = TransactionOptions::default();
+        assert!(is_retryable_number(1213));
+        assert!(is_retryable_number(1205));
+        //唯一索引冲突、语法错误以及非MySQL错误不重试
+        assert!(!options.should_retry(0, Some(1062)));
+        assert!(!options.should_retry(0, Some(1064)));
+        assert!(!options.should_retry(0, None));
+    }
+}
@@ -0,0 +1,242 @@
+//! `#[validate(...)]`字段校验，以及派生宏生成的校验代码所调用的辅助函数
+
+use std::error::Error as StdError;
+use std::fmt::{self, Display};
+use std::sync::OnceLock;
+
+pub use regex::Regex;
+
+/// 单个字段的校验错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldError {
+    /// struct中的字段名字
+    pub field: &'static str,
+    /// 校验规则：`length`、`range`、`regex`、`custom`
+    pub code: &'static str,
+    pub message: String,
+}
+
+/// 校验失败时返回的所有字段错误
+///
+/// 生成的`insert`、`update`等方法会返回`sqlx_sqlhelper::Error::Validation`。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationErrors {
+    pub errors: Vec<FieldError>,
+}
+
+impl ValidationErrors {
+    pub fn add(&mut self, field: &'static str, code: &'static str, message: impl Into<String>) {
+        self.errors.push(FieldError {
+            field,
+            code,
+            message: message.into(),
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// 没有错误时返回`Ok(())`
+    pub fn into_result(self) -> Result<(), Self> {
+        match self.is_empty() {
+            true => Ok(()),
+            false => Err(self),
+        }
+    }
+}
+
+impl Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "validation failed")?;
+        for (i, error) in self.errors.iter().enumerate() {
+            let sep = if i == 0 { ": " } else { "; " };
+            write!(f, "{}{} {}", sep, error.field, error.message)?;
+        }
+        Ok(())
+    }
+}
+
+impl StdError for ValidationErrors {}
+
+/// 校验字符长度
+pub fn length<T: AsRef<str> + ?Sized>(
+    errors: &mut ValidationErrors,
+    field: &'static str,
+    value: &T,
+    min: Option<usize>,
+    max: Option<usize>,
+) {
+    let len = value.as_ref().chars().count();
+    if let Some(min) = min {
+        if len < min {
+            errors.add(field, "length", format!("length must be at least {}", min));
+        }
+    }
+    if let Some(max) = max {
+        if len > max {
+            errors.add(field, "length", format!("length must be at most {}", max));
+        }
+    }
+}
+
+/// 校验取值范围
+pub fn range<T: PartialOrd + Display>(
+    errors: &mut ValidationErrors,
+    field: &'static str,
+    value: &T,
+    min: Option<T>,
+    max: Option<T>,
+) {
+    if let Some(min) = min {
+        if *value < min {
+            errors.add(field, "range", format!("must be at least {}", min));
+        }
+    }
+    if let Some(max) = max {
+        if *value > max {
+            errors.add(field, "range", format!("must be at most {}", max));
+        }
+    }
+}
+
+/// 校验正则表达式，`regex`第一次使用时编译
+///
+/// `#[validate(regex = "...")]`的正则表达式在编译期已经检查过，`pattern`无效时会panic
+pub fn regex<T: AsRef<str> + ?Sized>(
+    errors: &mut ValidationErrors,
+    field: &'static str,
+    value: &T,
+    regex: &OnceLock<Regex>,
+    pattern: &'static str,
+) {
+    let regex = regex.get_or_init(|| {
+        Regex::new(pattern).unwrap_or_else(|e| panic!("invalid regex `{}`: {}", pattern, e))
+    });
+    if !regex.is_match(value.as_ref()) {
+        errors.add(field, "regex", format!("must match `{}`", pattern));
+    }
+}
+
+/// 调用自定义校验函数，函数返回`Err(message)`表示校验失败
+///
+/// `String`字段传入`&str`，其他字段传入`&T`
+pub fn custom<T: ?Sized>(
+    errors: &mut ValidationErrors,
+    field: &'static str,
+    value: &T,
+    f: impl FnOnce(&T) -> Result<(), String>,
+) {
+    if let Err(message) = f(value) {
+        errors.add(field, "custom", message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(errors: &ValidationErrors) -> Vec<(&str, &str)> {
+        errors
+            .errors
+            .iter()
+            .map(|error| (error.field, error.message.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn length_counts_chars() {
+        let mut errors = ValidationErrors::default();
+        //按字符而不是字节计算长度
+        length(&mut errors, "nick", "张三", Some(2), Some(2));
+        assert!(errors.is_empty());
+        length(&mut errors, "nick", "", Some(1), None);
+        length(&mut errors, "account", &"abcdef".to_string(), None, Some(5));
+        assert_eq!(
+            messages(&errors),
+            vec![
+                ("nick", "length must be at least 1"),
+                ("account", "length must be at most 5")
+            ]
+        );
+        assert!(errors.errors.iter().all(|error| error.code == "length"));
+    }
+
+    #[test]
+    fn range_bounds() {
+        let mut errors = ValidationErrors::default();
+        range(&mut errors, "age", &0, Some(0), Some(150));
+        range(&mut errors, "age", &150, Some(0), Some(150));
+        assert!(errors.is_empty());
+        range(&mut errors, "age", &-1, Some(0), None);
+        range(&mut errors, "score", &1.5, None, Some(1.0));
+        assert_eq!(
+            messages(&errors),
+            vec![
+                ("age", "must be at least 0"),
+                ("score", "must be at most 1")
+            ]
+        );
+        assert!(errors.errors.iter().all(|error| error.code == "range"));
+    }
+
+    #[test]
+    fn regex_compiled_once() {
+        static REGEX: OnceLock<Regex> = OnceLock::new();
+        let mut errors = ValidationErrors::default();
+        regex(&mut errors, "account", "admin", &REGEX, "^[a-z]+$");
+        assert!(errors.is_empty());
+        regex(&mut errors, "account", "Admin", &REGEX, "^[a-z]+$");
+        assert_eq!(
+            messages(&errors),
+            vec![("account", "must match `^[a-z]+$`")]
+        );
+        assert_eq!(errors.errors[0].code, "regex");
+        assert_eq!(REGEX.get().unwrap().as_str(), "^[a-z]+$");
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid regex `[a-z`")]
+    fn regex_invalid_pattern() {
+        static REGEX: OnceLock<Regex> = OnceLock::new();
+        regex(
+            &mut ValidationErrors::default(),
+            "account",
+            "a",
+            &REGEX,
+            "[a-z",
+        );
+    }
+
+    #[test]
+    fn custom_fn() {
+        fn not_admin(value: &str) -> Result<(), String> {
+            match value == "admin" {
+                true => Err("reserved".to_string()),
+                false => Ok(()),
+            }
+        }
+        let mut errors = ValidationErrors::default();
+        custom(&mut errors, "account", "guest", not_admin);
+        assert!(errors.is_empty());
+        custom(
+            &mut errors,
+            "account",
+            "admin".to_string().as_str(),
+            not_admin,
+        );
+        custom(&mut errors, "age", &200u8, |age: &u8| match *age <= 150 {
+            true => Ok(()),
+            false => Err("too old".to_string()),
+        });
+        assert_eq!(
+            messages(&errors),
+            vec![("account", "reserved"), ("age", "too old")]
+        );
+        assert_eq!(
+            errors.to_string(),
+            "validation failed: account reserved; age too old"
+        );
+        assert!(errors.into_result().is_err());
+    }
+}